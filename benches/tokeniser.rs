@@ -0,0 +1,47 @@
+//! Tokenises generated scripts of growing size and prints the time spent per kilobyte.
+//! The tokeniser is linear if the time per kilobyte stays roughly the same for every size.
+//!
+//! Run with `cargo bench --bench tokeniser`.
+
+use std::{hint::black_box, time::Instant};
+
+use nolang::{source_code::SourceCode, tokeniser};
+
+const SIZES: [usize; 5] = [25, 50, 100, 200, 400];
+const RUNS: u32 = 5;
+
+fn script(kilobytes: usize) -> String {
+  let mut code = String::new();
+  let mut line = 0;
+
+  while code.len() < kilobytes * 1024 {
+    code += &format!(
+      "let value_{} = (value * 2.5 + 0x1f) / 3 // größe\nname = \"line {}\" + 'ü' /* {} */\n",
+      line, line, line
+    );
+    line += 1;
+  }
+
+  code
+}
+
+fn main() {
+  for kilobytes in SIZES {
+    let source_code = SourceCode::new(script(kilobytes));
+    let mut tokens = 0;
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+      tokens = black_box(tokeniser::from_source_code(black_box(&source_code))).len();
+    }
+    let elapsed = start.elapsed() / RUNS;
+
+    println!(
+      "{:>4} KB: {:>7} tokens in {:>10.2?}, {:>8.2?} per KB",
+      kilobytes,
+      tokens,
+      elapsed,
+      elapsed / kilobytes as u32
+    );
+  }
+}
@@ -1,39 +1,88 @@
-use super::literal::Literal;
-use std::fmt::Display;
+use super::{
+  literal::Literal,
+  operator::{BinaryOp, IncrementOp, UnaryOp},
+};
+use std::{fmt::Display, rc::Rc};
 
 use crate::{
   source_code::Location,
-  tokeniser::{Token, TokenValue},
+  tokeniser::{self, Token, TokenValue},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ErrorCode {
+  InvalidToken(tokeniser::ErrorCode),
   TokenExpected,
   UnexpectedToken,
   RparenExpected,
+  RbracketExpected,
+  LbraceExpected,
+  RbraceExpected,
   AtomExpected,
   LiteralExpected,
+  IdentifierExpected,
+  LparenExpected,
+  AssignmentExpected,
+  ArrowExpected,
+  ColonExpected,
+  InExpected,
+  OutsideOfLoop(&'static str),
+  OutsideOfFunction(&'static str),
+  KeywordAssignment(String),
+  NestedTooDeeply(usize),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Value {
   Empty,
   Literal(Literal),
   Identifier(String),
-  Unary(String, Box<Ast>),
-  Binary(Box<Ast>, String, Box<Ast>),
+  Unary(UnaryOp, Box<Ast>),
+  Binary(Box<Ast>, BinaryOp, Box<Ast>),
   Error(ErrorCode),
   Expression(Box<Ast>),
   Assignment(Box<Ast>, Box<Ast>),
+  CompoundAssignment(Box<Ast>, BinaryOp, Box<Ast>),
+  PrefixIncrement(IncrementOp, Box<Ast>),
+  PostfixIncrement(Box<Ast>, IncrementOp),
+  Call(Box<Ast>, Vec<Ast>),
+  /// Bodies of lambdas and function definitions are shared with the functions computed from them
+  Lambda(Vec<Ast>, Rc<Ast>),
+  List(Vec<Ast>),
+  Conditional(Box<Ast>, Box<Ast>, Box<Ast>),
+  If(Box<Ast>, Box<Ast>, Option<Box<Ast>>),
+  Block(Vec<Ast>),
+  While(Box<Ast>, Box<Ast>),
+  For(Box<Ast>, Box<Ast>, Box<Ast>),
+  Break,
+  Continue,
+  Return(Option<Box<Ast>>),
+  Declaration(Box<Ast>, Box<Ast>),
+  FunctionDefinition(Box<Ast>, Vec<Ast>, Rc<Ast>),
+  Program(Vec<Ast>),
 }
 
 impl Display for ErrorCode {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     let error = match self {
+      ErrorCode::InvalidToken(code) => return write!(f, "{}", code),
       ErrorCode::TokenExpected => "Expected Token",
       ErrorCode::RparenExpected => "Expected RPAREN",
+      ErrorCode::RbracketExpected => "Expected RBRACKET",
+      ErrorCode::LbraceExpected => "Expected LBRACE",
+      ErrorCode::RbraceExpected => "Expected RBRACE",
       ErrorCode::AtomExpected => "Expected Identifier or Literal",
       ErrorCode::LiteralExpected => "Expected Literal",
+      ErrorCode::IdentifierExpected => "Expected Identifier",
+      ErrorCode::LparenExpected => "Expected LPAREN",
+      ErrorCode::AssignmentExpected => "Expected =",
+      ErrorCode::ArrowExpected => "Expected =>",
+      ErrorCode::ColonExpected => "Expected :",
+      ErrorCode::InExpected => "Expected in",
+      ErrorCode::OutsideOfLoop(keyword) => return write!(f, "`{}` outside of a loop", keyword),
+      ErrorCode::OutsideOfFunction(keyword) => return write!(f, "`{}` outside of a function", keyword),
+      ErrorCode::KeywordAssignment(keyword) => return write!(f, "Cannot assign to keyword `{}`", keyword),
+      ErrorCode::NestedTooDeeply(limit) => return write!(f, "Code is nested deeper than {} levels", limit),
       ErrorCode::UnexpectedToken => "Unexpected Token",
     };
 
@@ -41,6 +90,10 @@ impl Display for ErrorCode {
   }
 }
 
+fn join(items: &[Ast], separator: &str) -> String {
+  items.iter().map(|item| item.to_string()).collect::<Vec<_>>().join(separator)
+}
+
 impl Display for Value {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     let value = match self {
@@ -48,10 +101,31 @@ impl Display for Value {
       Value::Literal(t) => t.to_string(),
       Value::Identifier(s) => s.to_string(),
       Value::Unary(op, e) => format!("{}{}", op, e),
+      Value::Binary(left, BinaryOp::Range, right) => format!("{}..{}", left, right),
       Value::Binary(left, op, right) => format!("{} {} {}", left, op, right),
-      Value::Expression(e) => format!("({})", e.to_string()),
+      Value::Expression(e) => format!("({})", e),
       Value::Error(err) => format!("{}", err),
       Value::Assignment(id, expr) => format!("{} = {}", id, expr),
+      Value::CompoundAssignment(id, op, expr) => format!("{} {}= {}", id, op, expr),
+      Value::PrefixIncrement(op, id) => format!("{}{}", op, id),
+      Value::PostfixIncrement(id, op) => format!("{}{}", id, op),
+      Value::Call(callee, args) => format!("{}({})", callee, join(args, ", ")),
+      Value::Lambda(params, body) => format!("({}) => {}", join(params, ", "), body),
+      Value::List(items) => format!("[{}]", join(items, ", ")),
+      Value::Conditional(condition, then, otherwise) => format!("{} ? {} : {}", condition, then, otherwise),
+      Value::If(condition, then, Some(otherwise)) => format!("if {} {} else {}", condition, then, otherwise),
+      Value::If(condition, then, None) => format!("if {} {}", condition, then),
+      Value::Block(lines) if lines.is_empty() => "{}".to_string(),
+      Value::Block(lines) => format!("{{ {} }}", join(lines, "; ")),
+      Value::While(condition, body) => format!("while {} {}", condition, body),
+      Value::For(id, iterable, body) => format!("for {} in {} {}", id, iterable, body),
+      Value::Break => "break".to_string(),
+      Value::Continue => "continue".to_string(),
+      Value::Return(Some(expr)) => format!("return {}", expr),
+      Value::Return(None) => "return".to_string(),
+      Value::Declaration(id, expr) => format!("let {} = {}", id, expr),
+      Value::FunctionDefinition(name, params, body) => format!("fn {}({}) = {}", name, join(params, ", "), body),
+      Value::Program(lines) => join(lines, "; "),
     };
     write!(f, "{}", value)
   }
@@ -64,7 +138,9 @@ impl Value {
       TokenValue::IntLiteral(i) => Literal::Int(i),
       TokenValue::FloatLiteral(f) => Literal::Float(f),
       TokenValue::StringLiteral(s) => Literal::String(s),
-      _ => panic!("{} cannot be used to produce a literal", token),
+      TokenValue::Keyword(k) if k == "true" || k == "false" => Literal::Bool(k == "true"),
+      TokenValue::Keyword(k) if k == "null" => Literal::Null,
+      _ => return Value::Error(ErrorCode::LiteralExpected),
     };
 
     Value::Literal(value)
@@ -73,27 +149,17 @@ impl Value {
   pub fn from_identifier(token: &Token) -> Self {
     let value = match token.value.clone() {
       TokenValue::Identifier(id) => id,
-      _ => panic!("{} cannot be used to produce a identifier", token),
+      _ => return Value::Error(ErrorCode::IdentifierExpected),
     };
 
     Value::Identifier(value)
   }
 
-  pub fn from_unary(op: TokenValue, expr: Ast) -> Self {
-    let op = match op {
-      TokenValue::Operator(op) => op,
-      _ => panic!("{} cannot be used to produce an unary expression", op),
-    };
-
+  pub fn from_unary(op: UnaryOp, expr: Ast) -> Self {
     Value::Unary(op, Box::new(expr))
   }
 
-  pub fn from_binary(left: Ast, op: TokenValue, right: Ast) -> Self {
-    let op = match op {
-      TokenValue::Operator(op) => op,
-      _ => panic!("{} cannot be used to produce an binary expresseion", op),
-    };
-
+  pub fn from_binary(left: Ast, op: BinaryOp, right: Ast) -> Self {
     Value::Binary(Box::new(left), op, Box::new(right))
   }
 
@@ -105,12 +171,72 @@ impl Value {
     Value::Assignment(Box::new(id), Box::new(expr))
   }
 
+  pub fn from_compound_assignment(id: Ast, op: BinaryOp, expr: Ast) -> Self {
+    Value::CompoundAssignment(Box::new(id), op, Box::new(expr))
+  }
+
+  pub fn from_prefix_increment(op: IncrementOp, id: Ast) -> Self {
+    Value::PrefixIncrement(op, Box::new(id))
+  }
+
+  pub fn from_postfix_increment(id: Ast, op: IncrementOp) -> Self {
+    Value::PostfixIncrement(Box::new(id), op)
+  }
+
+  pub fn from_call(callee: Ast, args: Vec<Ast>) -> Self {
+    Value::Call(Box::new(callee), args)
+  }
+
+  pub fn from_lambda(params: Vec<Ast>, body: Ast) -> Self {
+    Value::Lambda(params, Rc::new(body))
+  }
+
+  pub fn from_list(items: Vec<Ast>) -> Self {
+    Value::List(items)
+  }
+
+  pub fn from_conditional(condition: Ast, then: Ast, otherwise: Ast) -> Self {
+    Value::Conditional(Box::new(condition), Box::new(then), Box::new(otherwise))
+  }
+
+  pub fn from_if(condition: Ast, then: Ast, otherwise: Option<Ast>) -> Self {
+    Value::If(Box::new(condition), Box::new(then), otherwise.map(Box::new))
+  }
+
+  pub fn from_block(lines: Vec<Ast>) -> Self {
+    Value::Block(lines)
+  }
+
+  pub fn from_while(condition: Ast, body: Ast) -> Self {
+    Value::While(Box::new(condition), Box::new(body))
+  }
+
+  pub fn from_for(id: Ast, iterable: Ast, body: Ast) -> Self {
+    Value::For(Box::new(id), Box::new(iterable), Box::new(body))
+  }
+
+  pub fn from_return(expr: Option<Ast>) -> Self {
+    Value::Return(expr.map(Box::new))
+  }
+
+  pub fn from_declaration(id: Ast, expr: Ast) -> Self {
+    Value::Declaration(Box::new(id), Box::new(expr))
+  }
+
+  pub fn from_function_definition(name: Ast, params: Vec<Ast>, body: Ast) -> Self {
+    Value::FunctionDefinition(Box::new(name), params, Rc::new(body))
+  }
+
   pub fn from_expression(expr: Ast) -> Self {
     Value::Expression(Box::new(expr))
   }
+
+  pub fn from_program(lines: Vec<Ast>) -> Self {
+    Value::Program(lines)
+  }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Ast {
   pub value: Value,
   pub start: Location,
@@ -135,17 +261,13 @@ impl Ast {
   }
 
   pub fn from_value(value: Value, start: Location, end: Location) -> Ast {
-    Ast {
-      value,
-      start: start,
-      end: end,
-    }
+    Ast { value, start, end }
   }
 
   pub fn start(&self) -> Location {
-    self.start.clone()
+    self.start
   }
   pub fn end(&self) -> Location {
-    self.end.clone()
+    self.end
   }
 }
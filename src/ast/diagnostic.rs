@@ -0,0 +1,28 @@
+use std::fmt::Display;
+
+use super::ast::ErrorCode;
+use crate::source_code::{Location, SourceMap};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+  pub code: ErrorCode,
+  pub start: Location,
+  pub end: Location,
+}
+
+impl Display for Diagnostic {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}: {}", self.start, self.code)
+  }
+}
+
+impl Diagnostic {
+  pub fn new(code: ErrorCode, start: Location, end: Location) -> Diagnostic {
+    Diagnostic { code, start, end }
+  }
+
+  /// Like `to_string`, but with the name of the file in front, as `file:line:col: message`
+  pub fn describe(&self, source_map: &SourceMap) -> String {
+    source_map.describe(self.start, &self.code)
+  }
+}
@@ -1,11 +1,13 @@
 use std::fmt::Display;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Literal {
   Char(char),
   Int(u128),
   Float(f64),
   String(String),
+  Bool(bool),
+  Null,
 }
 
 impl Display for Literal {
@@ -15,6 +17,8 @@ impl Display for Literal {
       Literal::Int(u) => u.to_string(),
       Literal::Float(f) => f.to_string(),
       Literal::String(s) => s.to_string(),
+      Literal::Bool(b) => b.to_string(),
+      Literal::Null => "null".to_string(),
     };
     write!(f, "{}", r)
   }
@@ -1,16 +1,37 @@
 mod ast;
+mod diagnostic;
 mod literal;
+mod operator;
 mod parser;
+mod precedence;
+mod visit;
 
-use crate::{source_code, tokeniser};
+use crate::{
+  source_code::{self, SourceCode},
+  tokeniser::{Token, Tokeniser},
+};
 pub use ast::{Ast, ErrorCode, Value};
+pub use diagnostic::Diagnostic;
 pub use literal::Literal;
-pub use parser::from_tokens;
+pub use operator::{BinaryOp, IncrementOp, UnaryOp};
+pub use parser::Statements;
+pub use visit::{Fold, Visitor, VisitorMut};
+
+pub fn from_source_code(source_code: &SourceCode) -> Ast {
+  parser::from_tokens(Tokeniser::new(source_code))
+}
+
+pub fn parse(source_code: &SourceCode) -> (Ast, Vec<Diagnostic>) {
+  parser::parse(Tokeniser::new(source_code))
+}
+
+/// Parses the tokens lazily, one statement at a time, for callers which compute code while it is still being read
+pub fn statements<I: Iterator<Item = Token>>(tokens: impl IntoIterator<IntoIter = I>) -> Statements<I> {
+  Statements::new(tokens)
+}
 
 pub fn from_string(code: &str) -> Ast {
   let source_code = source_code::SourceCode::new(code.to_string());
-  let tokens = tokeniser::from_source_code(&source_code);
-  let ast = parser::from_tokens(tokens);
 
-  ast
+  from_source_code(&source_code)
 }
@@ -0,0 +1,151 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+  Plus,
+  Minus,
+  Not,
+  BitNot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Rem,
+  Pow,
+  Eq,
+  Ne,
+  Lt,
+  Gt,
+  Le,
+  Ge,
+  BitAnd,
+  BitOr,
+  BitXor,
+  Shl,
+  Shr,
+  And,
+  Or,
+  Range,
+  Pipe,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncrementOp {
+  Increment,
+  Decrement,
+}
+
+impl UnaryOp {
+  pub fn symbol(&self) -> &'static str {
+    match self {
+      UnaryOp::Plus => "+",
+      UnaryOp::Minus => "-",
+      UnaryOp::Not => "!",
+      UnaryOp::BitNot => "~",
+    }
+  }
+}
+
+impl BinaryOp {
+  pub fn from_symbol(symbol: &str) -> Option<Self> {
+    let op = match symbol {
+      "+" => BinaryOp::Add,
+      "-" => BinaryOp::Sub,
+      "*" => BinaryOp::Mul,
+      "/" => BinaryOp::Div,
+      "%" => BinaryOp::Rem,
+      "**" => BinaryOp::Pow,
+      "==" => BinaryOp::Eq,
+      "!=" => BinaryOp::Ne,
+      "<" => BinaryOp::Lt,
+      ">" => BinaryOp::Gt,
+      "<=" => BinaryOp::Le,
+      ">=" => BinaryOp::Ge,
+      "&" => BinaryOp::BitAnd,
+      "|" => BinaryOp::BitOr,
+      "^" => BinaryOp::BitXor,
+      "<<" => BinaryOp::Shl,
+      ">>" => BinaryOp::Shr,
+      "&&" => BinaryOp::And,
+      "||" => BinaryOp::Or,
+      ".." => BinaryOp::Range,
+      "|>" => BinaryOp::Pipe,
+      _ => return None,
+    };
+
+    Some(op)
+  }
+
+  /// Parses the operator of a compound assignment, like `+` in `a += 1`
+  pub fn from_compound_symbol(symbol: &str) -> Option<Self> {
+    match symbol.strip_suffix('=').and_then(BinaryOp::from_symbol) {
+      Some(op @ (BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem)) => Some(op),
+      _ => None,
+    }
+  }
+
+  pub fn symbol(&self) -> &'static str {
+    match self {
+      BinaryOp::Add => "+",
+      BinaryOp::Sub => "-",
+      BinaryOp::Mul => "*",
+      BinaryOp::Div => "/",
+      BinaryOp::Rem => "%",
+      BinaryOp::Pow => "**",
+      BinaryOp::Eq => "==",
+      BinaryOp::Ne => "!=",
+      BinaryOp::Lt => "<",
+      BinaryOp::Gt => ">",
+      BinaryOp::Le => "<=",
+      BinaryOp::Ge => ">=",
+      BinaryOp::BitAnd => "&",
+      BinaryOp::BitOr => "|",
+      BinaryOp::BitXor => "^",
+      BinaryOp::Shl => "<<",
+      BinaryOp::Shr => ">>",
+      BinaryOp::And => "&&",
+      BinaryOp::Or => "||",
+      BinaryOp::Range => "..",
+      BinaryOp::Pipe => "|>",
+    }
+  }
+}
+
+impl IncrementOp {
+  pub fn symbol(&self) -> &'static str {
+    match self {
+      IncrementOp::Increment => "++",
+      IncrementOp::Decrement => "--",
+    }
+  }
+
+  /// Returns the operator which updates the variable by one
+  pub fn binary(&self) -> BinaryOp {
+    match self {
+      IncrementOp::Increment => BinaryOp::Add,
+      IncrementOp::Decrement => BinaryOp::Sub,
+    }
+  }
+}
+
+impl Display for UnaryOp {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.symbol())
+  }
+}
+
+impl Display for BinaryOp {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.symbol())
+  }
+}
+
+impl Display for IncrementOp {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.symbol())
+  }
+}
@@ -1,235 +1,885 @@
-use std::cmp;
+use std::collections::VecDeque;
 
-use crate::tokeniser::{Token, TokenValue};
+use crate::{
+  source_code::Location,
+  stack::MAX_PARSE_DEPTH,
+  tokeniser::{Token, TokenValue},
+};
 
-use super::ast::{Ast, ErrorCode, Value};
+use super::{
+  ast::{Ast, ErrorCode, Value},
+  diagnostic::Diagnostic,
+  operator::BinaryOp,
+  precedence::{self, Operators, PrefixOp},
+};
 
-pub struct Parser {
-  tokens: Vec<Token>,
-  position: usize,
-  pstack: Vec<usize>,
+/// Parses tokens as they are pulled from the iterator, looking at most two tokens ahead
+pub struct Parser<I: Iterator<Item = Token>> {
+  tokens: I,
+  /// Tokens pulled from the iterator but not consumed yet, the first one is the current token
+  lookahead: VecDeque<Token>,
+  previous: Option<Token>,
+  /// Operators written like the current token, looked up once when the token becomes current
+  operators: Option<&'static Operators>,
+  consumed: usize,
+  /// Start locations of the nodes being parsed
+  pstack: Vec<Location>,
+  depth: usize,
+  nesting: usize,
+  blocks: usize,
+  loops: usize,
+  functions: usize,
+  diagnostics: Vec<Diagnostic>,
 }
 
 macro_rules! can_consume {
   ($self: expr, $match: path) => {{
-    let token = $self.tokens.get($self.position);
-    if let Some(unwrapped) = token {
-      match unwrapped.value.clone() {
-        $match(_) => {
-          $self.position += 1;
-          token
-        }
-        _ => None,
-      }
+    if matches!($self.current_token(), Some(Token { value: $match(_), .. })) {
+      $self.advance();
+      $self.previous_token()
     } else {
       None
     }
   }};
 
   ($self: expr, $match: path[$value: expr]) => {{
-    let token = $self.tokens.get($self.position);
-    if let Some(unwrapped) = token {
-      match unwrapped.value.clone() {
-        $match(id) if id == $value => {
-          $self.position += 1;
-          token
-        }
-        _ => None,
-      }
+    if matches!($self.current_token(), Some(Token { value: $match(id), .. }) if *id == $value) {
+      $self.advance();
+      $self.previous_token()
     } else {
       None
     }
   }};
 
   ($self: expr, $match: path { $cond: expr }) => {{
-    let token = $self.tokens.get($self.position);
-    if let Some(unwrapped) = token {
-      match unwrapped.value.clone() {
-        $match(val) if $cond(&val) => {
-          $self.position += 1;
-          token
-        }
-        _ => None,
-      }
+    if matches!($self.current_token(), Some(Token { value: $match(val), .. }) if $cond(val)) {
+      $self.advance();
+      $self.previous_token()
     } else {
       None
     }
   }};
 }
 
-impl Parser {
-  pub fn new(tokens: Vec<Token>) -> Self {
+impl<I: Iterator<Item = Token>> Parser<I> {
+  pub fn new(tokens: impl IntoIterator<IntoIter = I>) -> Self {
+    let mut tokens = tokens.into_iter();
+    let lookahead: VecDeque<_> = tokens.next().into_iter().collect();
+    let operators = lookahead.front().and_then(Self::find_operators);
+
     Parser {
       tokens,
-      position: 0,
+      lookahead,
+      previous: None,
+      operators,
+      consumed: 0,
       pstack: vec![],
+      depth: 0,
+      nesting: 0,
+      blocks: 0,
+      loops: 0,
+      functions: 0,
+      diagnostics: vec![],
     }
   }
 
   fn current_token(&self) -> Option<&Token> {
-    self.tokens.get(self.position)
+    self.lookahead.front()
   }
 
-  fn start_node(&mut self) {
-    self.pstack.push(self.position);
+  /// Pulls the token after the current one, which is needed to tell assignments from expressions
+  fn next_token(&mut self) -> Option<&Token> {
+    if self.lookahead.len() < 2 {
+      self.lookahead.extend(self.tokens.next());
+    }
+
+    self.lookahead.get(1)
+  }
+
+  fn previous_token(&self) -> Option<&Token> {
+    self.previous.as_ref()
+  }
+
+  fn advance(&mut self) {
+    if let Some(token) = self.lookahead.pop_front() {
+      self.previous = Some(token);
+      self.consumed += 1;
+    }
+
+    if self.lookahead.is_empty() {
+      self.lookahead.extend(self.tokens.next());
+    }
+
+    self.operators = self.current_token().and_then(Self::find_operators);
+  }
+
+  fn find_operators(token: &Token) -> Option<&'static Operators> {
+    match &token.value {
+      TokenValue::Operator(op) => precedence::find(op),
+      _ => None,
+    }
   }
 
-  fn restore(&mut self) {
-    self.position = self.pstack.pop().unwrap();
+  fn is_separator(token: &Token) -> bool {
+    matches!(&token.value, TokenValue::Operator(op) if op == ";")
+  }
+
+  fn is_block_end(token: &Token) -> bool {
+    matches!(&token.value, TokenValue::Bracket('}'))
+  }
+
+  /// Tokens the parser can resume from after an error, so they are never swallowed as unexpected
+  fn is_recovery_point(token: &Token) -> bool {
+    Self::is_separator(token)
+      || matches!(&token.value, TokenValue::Bracket(')' | ']' | '}'))
+      || matches!(&token.value, TokenValue::Operator(op) if op == ",")
+  }
+
+  /// Checks if the current token starts on the same line as the previous one ends.
+  /// Line breaks are insignificant inside brackets.
+  fn continues_line(&self) -> bool {
+    match (self.previous_token(), self.current_token()) {
+      (Some(previous), Some(current)) => self.nesting > 0 || previous.end.line == current.start.line,
+      _ => false,
+    }
+  }
+
+  fn at_line_end(&self) -> bool {
+    match self.current_token() {
+      None => true,
+      Some(token) if Self::is_separator(token) => true,
+      Some(token) if self.blocks > 0 && Self::is_block_end(token) => true,
+      Some(_) => !self.continues_line(),
+    }
+  }
+
+  /// Location where the code after the consumed tokens starts, which is the end of the code if there are no tokens left
+  fn current_location(&self) -> Location {
+    match (self.current_token(), self.previous_token()) {
+      (Some(token), _) => token.start,
+      (None, Some(token)) => token.end,
+      (None, None) => Location::default(),
+    }
+  }
+
+  fn start_node(&mut self) {
+    self.pstack.push(self.current_location());
   }
 
   fn emit_node(&mut self, value: Value, advance: bool) -> Ast {
-    let max_index = self.tokens.len() - 1;
     let start = self.pstack.pop().unwrap();
-    let end = if advance { self.position } else { self.position - 1 };
-
-    let start = cmp::min(start, max_index);
-    let end = cmp::min(end, max_index);
 
     if advance {
-      self.position += 1;
+      self.advance();
     }
 
-    Ast::from_value(value, self.tokens[start].start, self.tokens[end].end)
+    let end = self.previous_token().map_or(start, |token| token.end);
+
+    Ast::from_value(value, start, end)
   }
 
-  fn parse_atom(&mut self) -> Ast {
-    self.start_node();
+  fn report(&mut self, code: ErrorCode, start: Location, end: Location) -> Ast {
+    self.diagnostics.push(Diagnostic::new(code.clone(), start, end));
 
+    Ast::from_value(Value::from_error(code), start, end)
+  }
+
+  /// Reports an error located at the current token without consuming it
+  fn report_at_current(&mut self, code: ErrorCode) -> Ast {
+    let (start, end) = match self.current_token() {
+      Some(token) => (token.start, token.end),
+      None => {
+        let end = self.current_location();
+        (end, end)
+      }
+    };
+
+    self.report(code, start, end)
+  }
+
+  fn error_code_for(token: &Token) -> ErrorCode {
+    match &token.value {
+      TokenValue::Error(_, code) => ErrorCode::InvalidToken(code.clone()),
+      _ => ErrorCode::UnexpectedToken,
+    }
+  }
+
+  /// Skips tokens up to the closing bracket matching an already consumed opening one.
+  /// Stops early at a statement separator or a mismatched closing bracket.
+  fn skip_to_closing_bracket(&mut self, closing: char) {
+    let mut depth = 0;
+
+    while let Some(token) = self.current_token() {
+      match token.value {
+        TokenValue::Bracket('(' | '[' | '{') => depth += 1,
+        TokenValue::Bracket(c) if depth == 0 => {
+          if c == closing {
+            self.advance();
+          }
+          return;
+        }
+        TokenValue::Bracket(_) => depth -= 1,
+        _ if depth == 0 && Self::is_separator(token) => return,
+        _ => {}
+      }
+
+      self.advance();
+    }
+  }
+
+  /// Parses a part of the code one level deeper, unless the code is already nested too deeply
+  fn parse_nested(&mut self, parse: impl FnOnce(&mut Self) -> Ast) -> Ast {
+    if self.depth >= MAX_PARSE_DEPTH {
+      return self.report_at_current(ErrorCode::NestedTooDeeply(MAX_PARSE_DEPTH));
+    }
+
+    self.depth += 1;
+    let ast = parse(self);
+    self.depth -= 1;
+
+    ast
+  }
+
+  fn parse_atom(&mut self) -> Ast {
     let value = match self.current_token() {
       Some(t) if t.is_identifier() => Value::from_identifier(t),
       Some(t) if t.is_literal() => Value::from_literal(t),
-      Some(_) => Value::from_error(ErrorCode::UnexpectedToken),
-      _ => Value::from_error(ErrorCode::TokenExpected),
+      Some(t) if Self::is_recovery_point(t) => return self.report_at_current(ErrorCode::UnexpectedToken),
+      Some(t) => {
+        let code = Self::error_code_for(t);
+        let error = self.report_at_current(code);
+        self.advance();
+
+        return error;
+      }
+      None => return self.report_at_current(ErrorCode::TokenExpected),
     };
 
+    self.start_node();
     self.emit_node(value, true)
   }
 
+  /// Parses comma separated expressions up to the closing bracket, the opening one is already consumed.
+  /// Returns the error node if the closing bracket is missing.
+  fn parse_list(&mut self, closing: char) -> Result<Vec<Ast>, Box<Ast>> {
+    let errors = self.diagnostics.len();
+    let mut items = vec![];
+
+    self.nesting += 1;
+    if !matches!(self.current_token(), Some(Token { value: TokenValue::Bracket(c), .. }) if *c == closing) {
+      loop {
+        items.push(self.parse_expression());
+
+        if can_consume!(self, TokenValue::Operator[","]).is_none() {
+          break;
+        }
+      }
+    }
+    self.nesting -= 1;
+
+    if can_consume!(self, TokenValue::Bracket[closing]).is_none() {
+      // errors inside of the brackets are already reported, so only the recovery is needed
+      if errors == self.diagnostics.len() {
+        let code = match closing {
+          ']' => ErrorCode::RbracketExpected,
+          _ => ErrorCode::RparenExpected,
+        };
+        let error = self.report_at_current(code);
+        self.skip_to_closing_bracket(closing);
+
+        return Err(Box::new(error));
+      }
+
+      self.skip_to_closing_bracket(closing);
+    }
+
+    Ok(items)
+  }
+
+  /// Makes sure that function parameters are plain identifiers
+  fn check_parameters(&mut self, params: Vec<Ast>) -> Vec<Ast> {
+    params
+      .into_iter()
+      .map(|param| match param.value {
+        Value::Identifier(_) | Value::Error(_) => param,
+        _ => self.report(ErrorCode::IdentifierExpected, param.start(), param.end()),
+      })
+      .collect()
+  }
+
   fn parse_term(&mut self) -> Ast {
+    let keyword = |name| self.is_keyword(name);
+
+    if keyword("if") {
+      return self.parse_if();
+    } else if keyword("while") {
+      return self.parse_while();
+    } else if keyword("for") {
+      return self.parse_for();
+    } else if keyword("break") {
+      return self.parse_loop_control("break", Value::Break);
+    } else if keyword("continue") {
+      return self.parse_loop_control("continue", Value::Continue);
+    } else if matches!(
+      self.current_token(),
+      Some(Token {
+        value: TokenValue::Bracket('{'),
+        ..
+      })
+    ) {
+      return self.parse_block();
+    }
+
     self.start_node();
 
-    if let Some(_) = can_consume!(self, TokenValue::Bracket['(']) {
-      let expr = self.parse_expression();
-      let value = if let Some(_) = can_consume!(self, TokenValue::Bracket[')']) {
-        Value::from_expression(expr)
-      } else {
-        Value::from_error(ErrorCode::RparenExpected)
+    if can_consume!(self, TokenValue::Bracket['(']).is_some() {
+      let mut items = match self.parse_list(')') {
+        Ok(items) => items,
+        Err(error) => {
+          self.pstack.pop();
+          return *error;
+        }
       };
-      self.emit_node(value, false)
+
+      if can_consume!(self, TokenValue::Operator["=>"]).is_some() {
+        let params = self.check_parameters(items);
+        let body = self.parse_function_body();
+
+        self.emit_node(Value::from_lambda(params, body), false)
+      } else if items.len() == 1 {
+        self.emit_node(Value::from_expression(items.remove(0)), false)
+      } else {
+        self.pstack.pop();
+
+        self.report_at_current(ErrorCode::ArrowExpected)
+      }
+    } else if can_consume!(self, TokenValue::Bracket['[']).is_some() {
+      match self.parse_list(']') {
+        Ok(items) => self.emit_node(Value::from_list(items), false),
+        Err(error) => {
+          self.pstack.pop();
+          *error
+        }
+      }
     } else {
+      // atoms start their own node
+      self.pstack.pop();
+
       self.parse_atom()
     }
   }
 
-  fn parse_unary(&mut self) -> Ast {
+  fn is_keyword(&self, keyword: &str) -> bool {
+    self.current_token().is_some_and(|token| token.is_keyword(keyword))
+  }
+
+  /// Parses lines up to the closing brace. Line breaks are significant again, even if the block is inside brackets.
+  fn parse_block(&mut self) -> Ast {
     self.start_node();
 
-    if let Some(op) = can_consume!(self, TokenValue::Operator{ |val| val == "+" || val == "-" }) {
-      let op = op.value.clone();
-      let expr = self.parse_term();
-      let value = Value::from_unary(op, expr);
+    if can_consume!(self, TokenValue::Bracket['{']).is_none() {
+      self.pstack.pop();
+      return self.report_at_current(ErrorCode::LbraceExpected);
+    }
+
+    let nesting = std::mem::replace(&mut self.nesting, 0);
+    let mut lines = vec![];
+    self.blocks += 1;
 
-      self.emit_node(value, false)
-    } else {
-      self.parse_term()
+    loop {
+      while can_consume!(self, TokenValue::Operator[";"]).is_some() {}
+
+      match self.current_token() {
+        Some(token) if !Self::is_block_end(token) => self.parse_line(&mut lines),
+        _ => break,
+      }
     }
+
+    self.blocks -= 1;
+    self.nesting = nesting;
+
+    if can_consume!(self, TokenValue::Bracket['}']).is_none() {
+      self.pstack.pop();
+      return self.report_at_current(ErrorCode::RbraceExpected);
+    }
+
+    self.emit_node(Value::from_block(lines), false)
   }
 
-  fn parse_multiplication_rest(&mut self, left: Ast) -> Ast {
-    if let Some(op) = can_consume!(self, TokenValue::Operator { |val| "*/%".find(val).is_some() }) {
-      let op = op.value.clone();
-      let right = self.parse_unary();
-      let end = right.end();
-      let start = left.start();
+  fn parse_if(&mut self) -> Ast {
+    self.start_node();
+    self.advance();
 
-      let value = Value::from_binary(left, op, right);
+    let condition = self.parse_expression();
+    let then = self.parse_block();
 
-      let left = Ast::from_value(value, start, end);
+    let otherwise = if self.is_keyword("else") {
+      self.advance();
 
-      self.parse_multiplication_rest(left)
+      if self.is_keyword("if") {
+        Some(self.parse_nested(Self::parse_if))
+      } else {
+        Some(self.parse_block())
+      }
     } else {
-      left
+      None
+    };
+
+    self.emit_node(Value::from_if(condition, then, otherwise), false)
+  }
+
+  /// Parses the body of a loop, where `break` and `continue` are allowed
+  fn parse_loop_body(&mut self) -> Ast {
+    self.loops += 1;
+    let body = self.parse_block();
+    self.loops -= 1;
+
+    body
+  }
+
+  /// Parses the body of a function, where loops around the function cannot be controlled from
+  fn parse_function_body(&mut self) -> Ast {
+    let loops = std::mem::replace(&mut self.loops, 0);
+    self.functions += 1;
+    let body = self.parse_expression();
+    self.functions -= 1;
+    self.loops = loops;
+
+    body
+  }
+
+  fn parse_while(&mut self) -> Ast {
+    self.start_node();
+    self.advance();
+
+    let condition = self.parse_expression();
+    let body = self.parse_loop_body();
+
+    self.emit_node(Value::from_while(condition, body), false)
+  }
+
+  fn parse_for(&mut self) -> Ast {
+    self.start_node();
+    self.advance();
+
+    let id = match can_consume!(self, TokenValue::Identifier) {
+      Some(id) => Ast::from_identifier(id),
+      None => {
+        self.pstack.pop();
+        return self.report_at_current(ErrorCode::IdentifierExpected);
+      }
+    };
+
+    if can_consume!(self, TokenValue::Keyword["in"]).is_none() {
+      self.pstack.pop();
+      return self.report_at_current(ErrorCode::InExpected);
     }
+
+    let iterable = self.parse_expression();
+    let body = self.parse_loop_body();
+
+    self.emit_node(Value::from_for(id, iterable, body), false)
   }
 
-  fn parse_addition_rest(&mut self, left: Ast) -> Ast {
-    if let Some(op) = can_consume!(self, TokenValue::Operator { |val| "+-".find(val).is_some() }) {
-      let op = op.value.clone();
-      let right = self.parse_multiplication();
-      let end = right.end();
-      let start = left.start();
+  fn parse_loop_control(&mut self, keyword: &'static str, value: Value) -> Ast {
+    if self.loops == 0 {
+      let error = self.report_at_current(ErrorCode::OutsideOfLoop(keyword));
+      self.advance();
 
-      let value = Value::from_binary(left, op, right);
+      return error;
+    }
 
-      let left = Ast::from_value(value, start, end);
+    self.start_node();
+    self.emit_node(value, true)
+  }
 
-      self.parse_addition_rest(left)
-    } else {
-      left
+  /// Makes sure that increments and decrements are only applied to variables
+  fn check_increment_target(&mut self, target: Ast) -> Ast {
+    match target.value {
+      Value::Identifier(_) | Value::Error(_) => target,
+      _ => self.report(ErrorCode::IdentifierExpected, target.start(), target.end()),
     }
   }
 
-  fn parse_multiplication(&mut self) -> Ast {
-    let left = self.parse_unary();
+  fn parse_call_rest(&mut self, callee: Ast) -> Ast {
+    if !self.continues_line() || can_consume!(self, TokenValue::Bracket['(']).is_none() {
+      return callee;
+    }
+
+    let args = match self.parse_list(')') {
+      Ok(args) => args,
+      Err(error) => return *error,
+    };
+
+    let start = callee.start();
+    let end = self.previous_token().map_or(start, |token| token.end);
+    let call = Ast::from_value(Value::from_call(callee, args), start, end);
 
-    self.parse_multiplication_rest(left)
+    self.parse_nested(|parser| parser.parse_call_rest(call))
   }
 
-  fn parse_addition(&mut self) -> Ast {
-    let left = self.parse_multiplication();
+  fn parse_call(&mut self) -> Ast {
+    let term = self.parse_term();
 
-    self.parse_addition_rest(left)
+    self.parse_call_rest(term)
+  }
+
+  fn parse_prefix(&mut self) -> Ast {
+    let Some((op, power)) = self.operators.and_then(|operators| operators.prefix) else {
+      return self.parse_call();
+    };
+
+    self.start_node();
+    self.advance();
+
+    let operand = self.parse_nested(|parser| parser.parse_operators(power));
+    let value = match op {
+      PrefixOp::Increment(op) => Value::from_prefix_increment(op, self.check_increment_target(operand)),
+      PrefixOp::Unary(op) => Value::from_unary(op, operand),
+    };
+
+    self.emit_node(value, false)
+  }
+
+  /// Parses operators by their binding power from the precedence table.
+  /// Operators binding weaker than `min_power` are left for the callers, which parse the looser part of the expression.
+  fn parse_operators(&mut self, min_power: u8) -> Ast {
+    let mut left = self.parse_prefix();
+
+    while self.continues_line() {
+      let Some(operators) = self.operators else {
+        break;
+      };
+
+      if let Some((op, _)) = operators.postfix.filter(|(_, power)| *power >= min_power) {
+        self.advance();
+
+        let start = left.start();
+        let end = self.previous_token().map_or(left.end(), |token| token.end);
+        let id = self.check_increment_target(left);
+
+        left = Ast::from_value(Value::from_postfix_increment(id, op), start, end);
+      } else if let Some((op, _, right_power)) = operators.infix.filter(|(_, power, _)| *power >= min_power) {
+        self.advance();
+
+        // a chain like `a + b + c` is parsed in this loop, so only the right operand of each operator is nested
+        let right = self.parse_nested(|parser| parser.parse_operators(right_power));
+        let start = left.start();
+        let end = right.end();
+
+        left = Ast::from_value(Value::from_binary(left, op, right), start, end);
+      } else {
+        break;
+      }
+    }
+
+    left
+  }
+
+  fn parse_conditional(&mut self) -> Ast {
+    let condition = self.parse_operators(0);
+
+    if !self.continues_line() || can_consume!(self, TokenValue::Operator["?"]).is_none() {
+      return condition;
+    }
+
+    let then = self.parse_expression();
+
+    if !self.continues_line() || can_consume!(self, TokenValue::Operator[":"]).is_none() {
+      return self.report_at_current(ErrorCode::ColonExpected);
+    }
+
+    // the branch is parsed recursively, so `a ? b : c ? d : e` groups as `a ? b : (c ? d : e)`
+    let otherwise = self.parse_nested(Self::parse_conditional);
+    let start = condition.start();
+    let end = otherwise.end();
+
+    Ast::from_value(Value::from_conditional(condition, then, otherwise), start, end)
   }
 
   fn parse_expression(&mut self) -> Ast {
-    self.parse_addition()
+    self.parse_nested(Self::parse_conditional)
+  }
+
+  fn parse_function_definition(&mut self) -> Ast {
+    self.start_node();
+    self.advance();
+
+    let name = match can_consume!(self, TokenValue::Identifier) {
+      Some(name) => Ast::from_identifier(name),
+      None => {
+        self.pstack.pop();
+        return self.report_at_current(ErrorCode::IdentifierExpected);
+      }
+    };
+
+    if can_consume!(self, TokenValue::Bracket['(']).is_none() {
+      self.pstack.pop();
+      return self.report_at_current(ErrorCode::LparenExpected);
+    }
+
+    let params = match self.parse_list(')') {
+      Ok(params) => self.check_parameters(params),
+      Err(error) => {
+        self.pstack.pop();
+        return *error;
+      }
+    };
+
+    if can_consume!(self, TokenValue::Operator["="]).is_none() {
+      self.pstack.pop();
+      return self.report_at_current(ErrorCode::AssignmentExpected);
+    }
+
+    let body = self.parse_function_body();
+
+    self.emit_node(Value::from_function_definition(name, params, body), false)
+  }
+
+  fn parse_declaration(&mut self) -> Ast {
+    self.start_node();
+    self.advance();
+
+    let id = match can_consume!(self, TokenValue::Identifier) {
+      Some(id) => Ast::from_identifier(id),
+      None => {
+        self.pstack.pop();
+        return self.report_at_current(ErrorCode::IdentifierExpected);
+      }
+    };
+
+    if can_consume!(self, TokenValue::Operator["="]).is_none() {
+      self.pstack.pop();
+      return self.report_at_current(ErrorCode::AssignmentExpected);
+    }
+
+    let expr = self.parse_expression();
+
+    self.emit_node(Value::from_declaration(id, expr), false)
+  }
+
+  fn parse_return(&mut self) -> Ast {
+    if self.functions == 0 {
+      let error = self.report_at_current(ErrorCode::OutsideOfFunction("return"));
+      self.advance();
+
+      return error;
+    }
+
+    self.start_node();
+    self.advance();
+
+    let expr = match self.at_line_end() {
+      true => None,
+      false => Some(self.parse_expression()),
+    };
+
+    self.emit_node(Value::from_return(expr), false)
+  }
+
+  fn is_assignment_operator(val: &str) -> bool {
+    val == "=" || BinaryOp::from_compound_symbol(val).is_some()
+  }
+
+  /// Checks if the line assigns to the current token, which is decided by the assignment operator after it
+  fn is_assignment_to(&mut self, target: fn(&Token) -> bool) -> bool {
+    self.current_token().is_some_and(target)
+      && matches!(
+        self.next_token(),
+        Some(Token { value: TokenValue::Operator(op), .. }) if Self::is_assignment_operator(op)
+      )
+  }
+
+  /// Checks if the line assigns to a keyword, like `if = 3`
+  fn is_keyword_assignment(&mut self) -> bool {
+    self.is_assignment_to(|token| matches!(token.value, TokenValue::Keyword(_)))
   }
 
   fn parse_assignment_or_expression(&mut self) -> Ast {
+    if self.is_keyword("fn") {
+      return self.parse_function_definition();
+    } else if self.is_keyword("let") {
+      return self.parse_declaration();
+    } else if self.is_keyword("return") {
+      return self.parse_return();
+    } else if self.is_keyword_assignment() {
+      let keyword = self.current_token().map(|token| token.value.to_string()).unwrap_or_default();
+      let error = self.report_at_current(ErrorCode::KeywordAssignment(keyword));
+      self.advance();
+
+      return error;
+    }
+
+    if !self.is_assignment_to(Token::is_identifier) {
+      return self.parse_expression();
+    }
+
     self.start_node();
 
-    let id = can_consume!(self, TokenValue::Identifier);
-    let eq = can_consume!(self, TokenValue::Operator["="]);
+    let id = can_consume!(self, TokenValue::Identifier).map(Ast::from_identifier);
+    let op = match can_consume!(self, TokenValue::Operator) {
+      Some(Token {
+        value: TokenValue::Operator(op),
+        ..
+      }) => Some(op.clone()),
+      _ => None,
+    };
+    let compound = op.as_deref().and_then(BinaryOp::from_compound_symbol);
+    let expr = self.parse_expression();
+    let value = match (id, op.as_deref(), compound) {
+      (Some(id), Some("="), _) => Value::from_assignment(id, expr),
+      (Some(id), _, Some(op)) => Value::from_compound_assignment(id, op, expr),
+      _ => Value::from_error(ErrorCode::AssignmentExpected),
+    };
 
-    if let [Some(id), Some(_)] = [id, eq] {
-      let id = Ast::from_identifier(id);
-      let expr = self.parse_expression();
+    self.emit_node(value, false)
+  }
 
-      self.emit_node(Value::from_assignment(id, expr), false)
-    } else {
-      self.restore();
+  fn parse_line(&mut self, lines: &mut Vec<Ast>) {
+    let errors = self.diagnostics.len();
+    let consumed = self.consumed;
+
+    lines.push(self.parse_assignment_or_expression());
+
+    let (code, start, mut end) = match self.current_token() {
+      Some(token) if !self.at_line_end() => (Self::error_code_for(token), token.start, token.end),
+      // a line starting with a token which cannot start an expression is already reported, but has to be skipped
+      Some(_) if self.consumed == consumed => {
+        self.advance();
+        return;
+      }
+      _ => return,
+    };
+
+    while let Some(token) = self.current_token().filter(|_| !self.at_line_end()) {
+      end = token.end;
+      self.advance();
+    }
 
-      self.parse_expression()
+    // the rest of a line with errors is skipped silently, as it is most likely their consequence
+    if errors == self.diagnostics.len() {
+      lines.push(self.report(code, start, end));
     }
   }
 
+  /// Parses the next line into `lines`, or returns false if the code has ended
+  fn parse_next_line(&mut self, lines: &mut Vec<Ast>) -> bool {
+    while can_consume!(self, TokenValue::Operator[";"]).is_some() {}
+
+    if self.current_token().is_none() {
+      return false;
+    }
+
+    self.parse_line(lines);
+    true
+  }
+
   /*
-    Program ::= Line+
-    Line ::= Assignment | Expression ";"
-    Assignment ::= Identifier "=" Expression
-    Expression ::= Multiplication AdditionTail
-    AdditionTail ::= ["+" | "-"] Multiplication AdditionTail
-    Multiplication ::= UnaryExpression MultiplicationTail
-    MultiplicationTail ::= ["*" | "/" | "%"] UnaryExpression MultiplicationTail
-    UnaryExpression ::= ["+" | "-"] Term
-    Term ::= ("(" Expression ")") | Atom
-    Atom ::= Identifier | Literal
+    Program ::= Line*
+    Line ::= (FunctionDefinition | Declaration | Return | Assignment | Expression) (";" | NewLine | EOF)
+    FunctionDefinition ::= "fn" Identifier "(" [Identifier ("," Identifier)*] ")" "=" Expression
+    Declaration ::= "let" Identifier "=" Expression
+    Return ::= "return" [Expression]
+    Assignment ::= Identifier ["=" | "+=" | "-=" | "*=" | "/=" | "%="] Expression
+    Expression ::= Conditional
+    Conditional ::= Operation ["?" Expression ":" Conditional]
+    Operation ::= Prefix (InfixOperator Operation | PostfixOperator)*
+    Prefix ::= (PrefixOperator Operation) | Call
+    Call ::= Term ("(" [Expression ("," Expression)*] ")")*
+    Term ::= If | While | For | "break" | "continue" | Block | Lambda | List | ("(" Expression ")") | Atom
+    If ::= "if" Expression Block ["else" (If | Block)]
+    While ::= "while" Expression Block
+    For ::= "for" Identifier "in" Expression Block
+    Block ::= "{" (Line)* "}"
+    List ::= "[" [Expression ("," Expression)*] "]"
+    Lambda ::= "(" [Identifier ("," Identifier)*] ")" "=>" Expression
+    Atom ::= Identifier | Literal | "true" | "false" | "null"
+
+    Operations are grouped by the binding power and associativity of their operators in `precedence::OPERATORS`.
+    Increments and decrements can only be applied to identifiers.
   */
   pub fn parse(&mut self) -> Ast {
-    self.parse_assignment_or_expression()
+    let mut lines = vec![];
+
+    while self.parse_next_line(&mut lines) {}
+
+    let start = lines.first().map_or(Location::default(), |line| line.start());
+    let end = lines.last().map_or(Location::default(), |line| line.end());
+
+    Ast::from_value(Value::from_program(lines), start, end)
+  }
+}
+
+/// Parses the code one statement at a time, pulling only the tokens the statement needs.
+/// Every statement comes with the diagnostics reported while parsing it.
+pub struct Statements<I: Iterator<Item = Token>> {
+  parser: Parser<I>,
+  /// The rest of a line after an error, which is reported as a statement of its own
+  skipped: Option<(Ast, Vec<Diagnostic>)>,
+}
+
+impl<I: Iterator<Item = Token>> Statements<I> {
+  pub fn new(tokens: impl IntoIterator<IntoIter = I>) -> Self {
+    Statements {
+      parser: Parser::new(tokens),
+      skipped: None,
+    }
   }
 }
 
-pub fn from_tokens(tokens: Vec<Token>) -> Ast {
+impl<I: Iterator<Item = Token>> Iterator for Statements<I> {
+  type Item = (Ast, Vec<Diagnostic>);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if let Some(skipped) = self.skipped.take() {
+      return Some(skipped);
+    }
+
+    let mut lines = vec![];
+    if !self.parser.parse_next_line(&mut lines) {
+      return None;
+    }
+
+    let mut diagnostics = std::mem::take(&mut self.parser.diagnostics);
+    let mut lines = lines.into_iter();
+    let statement = lines.next()?;
+
+    // the rest of the line is only reported if the line itself had no errors, so the last diagnostic is its own
+    if let Some(skipped) = lines.next() {
+      self.skipped = Some((skipped, diagnostics.split_off(diagnostics.len().saturating_sub(1))));
+    }
+
+    Some((statement, diagnostics))
+  }
+}
+
+pub fn from_tokens(tokens: impl IntoIterator<Item = Token>) -> Ast {
   let mut parser = Parser::new(tokens);
 
   parser.parse()
 }
 
+pub fn parse(tokens: impl IntoIterator<Item = Token>) -> (Ast, Vec<Diagnostic>) {
+  let mut parser = Parser::new(tokens);
+  let ast = parser.parse();
+
+  (ast, parser.diagnostics)
+}
+
 #[cfg(test)]
 mod test {
-  use crate::ast;
+  use std::cell::Cell;
+
+  use super::{
+    precedence::{self, Fixity, OPERATORS},
+    Parser,
+  };
+  use crate::{
+    ast::{self, Ast, BinaryOp, Value},
+    source_code::{SourceCode, SourceMap},
+    stack,
+    tokeniser::Tokeniser,
+  };
 
   macro_rules! test {
     ($name: ident, $expr: expr) => {
@@ -271,4 +921,359 @@ mod test {
   test!(multiplication_with_integer_addition, "2 * (2 + 3)");
   test!(addition_multiple, "a + b * c + d");
   test!(incomplete_addition, "a + Expected");
+
+  test!(program_single_line, "a = 1;" => "a = 1");
+  test!(program_semicolons, "a = 1; b = 2;; a + b" => "a = 1; b = 2; a + b");
+  test!(program_newlines, "a = 2\nb = a * 3\n\na + b" => "a = 2; b = a * 3; a + b");
+  test!(program_expression_over_lines, "a = 2 *\n3" => "a = 2 * 3");
+  test!(program_operator_starts_line, "a = 2\n-3" => "a = 2; -3");
+  test!(program_newlines_in_brackets, "(a\n+ b)" => "(a + b)");
+  test!(program_empty, " \n ;" => "");
+  test!(comparison, "a + 1 < b * 2");
+  test!(comparison_chain, "a <= b >= c");
+  test!(equality_with_comparison, "a < b == c > d");
+  test!(logical_precedence, "a || b && c == d");
+  test!(logical_multiple, "a && b && c || d");
+  test!(logical_not, "!a && !(b || c)");
+  test!(assignment_with_logical, "c = a != b || a >= 0");
+  test!(bitwise, "a | b ^ c & d");
+  test!(bitwise_not, "~a & 127");
+  test!(shift, "a << 2 >> b");
+  test!(compound_assignment, "a += b * 2");
+  test!(compound_assignment_all, "a -= 1; b *= 2; c /= 3; d %= 4");
+  test!(prefix_increment, "++a + --b");
+  test!(postfix_increment, "a++ * b--");
+  test!(negated_increment, "-a++ + -++b");
+  test!(increment_on_new_line, "a\n++b" => "a; ++b");
+  test!(increment_of_literal, "++1" => "++Expected Identifier");
+  test!(increment_of_expression, "(a + b)++" => "Expected Identifier++");
+  test!(call_without_arguments, "f()");
+  test!(call_with_arguments, "max(a, b * 2, (c))");
+  test!(call_nested, "sqrt(pow(a, 2) + pow(b, 2))");
+  test!(call_chained, "f(a)(b)");
+  test!(call_in_expression, "-f(a) * 2 + g(b)++" => "-f(a) * 2 + Expected Identifier++");
+  test!(call_on_new_line, "f\n(a)" => "f; (a)");
+  test!(call_unclosed, "f(a b) + 1" => "Expected RPAREN + 1");
+  test!(function_definition, "fn area(w, h) = w * h");
+  test!(function_definition_without_parameters, "fn answer() = 42");
+  test!(function_definition_with_call, "fn hypot(a, b) = sqrt(a * a + b * b)");
+  test!(comments, "a = 1 // one\n/* two\n */ b = 2" => "a = 1; b = 2");
+  test!(comment_inside_brackets, "f(a, /* b, */ c)" => "f(a, c)");
+  test!(bool_and_null_literals, "a = true || false; b = null");
+  test!(declaration, "let a = 1 + 2");
+  test!(declaration_in_block, "{ let a = 1; a }");
+  test!(return_value, "fn f(x) = { if x < 0 { return 0 }; x }");
+  test!(return_without_value, "f = () => { return }");
+  test!(return_before_block_end, "fn f() = { return }");
+  test!(keyword_prefix_is_identifier, "iffy = 1; format = iffy");
+  test!(lambda, "area = (w, h) => w * h");
+  test!(lambda_without_parameters, "() => 42");
+  test!(lambda_called, "((x) => x * 2)(3)");
+  test!(lambda_returning_lambda, "(x) => (y) => x + y");
+  test!(list, "[1, a + 2, [b]]");
+  test!(list_empty, "[]");
+  test!(list_multiline, "[\n  1,\n  2\n]" => "[1, 2]");
+  test!(conditional, "a > 0 ? a : -a");
+  test!(conditional_right_associative, "a ? b : c ? d : e" => "a ? b : c ? d : e");
+  test!(conditional_nested_in_branch, "a ? b ? c : d : e");
+  test!(conditional_in_assignment, "x = a == 1 ? 2 : 3");
+  test!(conditional_in_lambda, "abs = (x) => x < 0 ? -x : x");
+  test!(conditional_on_new_line, "a\n? b : c" => "a; Unexpected Token");
+  test!(if_else, "if a > 0 { 1 } else { 2 }");
+  test!(if_without_else, "if a { b = 1 }");
+  test!(if_else_if, "if a { 1 } else if b { 2 } else { 3 }");
+  test!(if_empty_block, "if a {} else {}");
+  test!(if_in_expression, "x = 1 + if a { 1 } else { 2 }");
+  test!(if_multiline, "if a {\n  b = 1\n  b + 1\n}\nelse {\n  0\n}" => "if a { b = 1; b + 1 } else { 0 }");
+  test!(if_in_brackets, "(if a {\n  1\n  2\n})" => "(if a { 1; 2 })");
+  test!(range, "0..n + 1");
+  test!(range_in_conditional, "a ? 0..1 : 1..2");
+  test!(power, "a ** -b ** 2");
+  test!(pipe, "xs |> map |> f(a)");
+  test!(block, "{ a = 1; a + 1 }");
+  test!(block_multiline, "x = {\n  a = 1\n  a + 1\n}" => "x = { a = 1; a + 1 }");
+  test!(while_loop, "while i < 10 { i += 1 }");
+  test!(for_loop, "for i in 0..10 { s += i }");
+  test!(for_loop_over_list, "for x in [1, 2] { s += x }");
+  test!(break_and_continue, "while a { if b { break } else { continue } }");
+  test!(nested_loops, "for i in 0..3 { for j in 0..i { if j == 1 { break } } }");
+  test!(lambda_with_wrong_parameter, "(x, 1) => x" => "(x, Expected Identifier) => x");
+  test!(tuple_without_arrow, "(a, b) + c" => "Expected => + c");
+  test!(missing_operand, "(a + ) * b" => "(a + Unexpected Token) * b");
+  test!(missing_rparen, "(a b) + c" => "Expected RPAREN + c");
+  test!(invalid_token, "a + 'ab'" => "a + Char literal is too long");
+  test!(trailing_tokens, "a b c\nd" => "a; Unexpected Token; d");
+
+  /// Shows how operations are grouped by wrapping each of them in brackets
+  fn grouped(ast: &Ast) -> String {
+    match &ast.value {
+      Value::Program(lines) => lines.iter().map(grouped).collect::<Vec<_>>().join("; "),
+      Value::Binary(left, op, right) => format!("({} {} {})", grouped(left), op, grouped(right)),
+      Value::Unary(op, expr) => format!("({}{})", op, grouped(expr)),
+      Value::PrefixIncrement(op, expr) => format!("({}{})", op, grouped(expr)),
+      Value::PostfixIncrement(expr, op) => format!("({}{})", grouped(expr), op),
+      _ => ast.to_string(),
+    }
+  }
+
+  #[test]
+  pub fn groups_operators_by_precedence_table() {
+    let cases = [
+      ("a + b * c - d", "((a + (b * c)) - d)"),
+      ("a - b - c", "((a - b) - c)"),
+      ("a ** b ** c", "(a ** (b ** c))"),
+      ("-a ** 2", "(-(a ** 2))"),
+      ("a ** -b", "(a ** (-b))"),
+      ("-a++ * ++b ** 2", "((-(a++)) * ((++b) ** 2))"),
+      ("a | b ^ c & d == e", "(a | (b ^ (c & (d == e))))"),
+      ("a < b << 1 + c", "(a < (b << (1 + c)))"),
+      ("a || b && c", "(a || (b && c))"),
+      ("0..n || m", "((0 .. n) || m)"),
+      ("i < 0..n == r", "((i < (0 .. n)) == r)"),
+      ("0..n + 1", "(0 .. (n + 1))"),
+      ("xs |> f |> g", "((xs |> f) |> g)"),
+      ("a..b |> f", "((a .. b) |> f)"),
+    ];
+
+    for (code, expected) in cases {
+      assert_eq!(grouped(&ast::from_string(code)), expected, "{}", code);
+    }
+  }
+
+  #[test]
+  pub fn finds_every_operator_in_precedence_table_by_its_symbol() {
+    for op in OPERATORS {
+      let operators = precedence::find(op.symbol()).unwrap();
+      let found = match op.fixity {
+        Fixity::Prefix(node) => operators.prefix.map(|(found, _)| found == node),
+        Fixity::Infix(node, _) => operators.infix.map(|(found, _, _)| found == node),
+        Fixity::Postfix(node) => operators.postfix.map(|(found, _)| found == node),
+      };
+
+      assert_eq!(found, Some(true), "{}", op.symbol());
+    }
+  }
+
+  #[test]
+  pub fn rejects_unknown_operators() {
+    assert!(precedence::find("=>").is_none());
+    assert!(precedence::find("*").unwrap().prefix.is_none());
+    assert!(precedence::find("+").unwrap().postfix.is_none());
+    assert_eq!(BinaryOp::from_compound_symbol("=="), None);
+  }
+
+  fn diagnostics(code: &str) -> Vec<String> {
+    let (_, diagnostics) = ast::parse(&SourceCode::new(code.to_string()));
+
+    diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect()
+  }
+
+  #[test]
+  pub fn names_the_file_of_diagnostics() {
+    let mut source_map = SourceMap::new();
+    source_map.add(SourceCode::with_name("first.nl".to_string(), "a".to_string()));
+    let second = source_map.add(SourceCode::with_name("second.nl".to_string(), "a = (1".to_string()));
+    let (_, diagnostics) = ast::parse(&source_map[second]);
+    let diagnostics: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.describe(&source_map)).collect();
+
+    assert_eq!(diagnostics, vec!["second.nl:1:7: Expected RPAREN"]);
+  }
+
+  #[test]
+  pub fn reports_nothing_for_correct_code() {
+    assert!(diagnostics("a = (1 + 2) * 3\nb = a; a + b").is_empty());
+  }
+
+  #[test]
+  pub fn reports_every_error() {
+    let code = "a = 1 + * 2\nb = (2 3) + 1\nc = 'xx' + 1\nd = a b";
+
+    assert_eq!(
+      diagnostics(code),
+      vec![
+        "1:9: Unexpected Token",
+        "2:8: Expected RPAREN",
+        "3:5: Char literal is too long",
+        "4:7: Unexpected Token"
+      ]
+    );
+  }
+
+  #[test]
+  pub fn reports_missing_token_at_the_end() {
+    assert_eq!(diagnostics("a = (1 +"), vec!["1:9: Expected Token"]);
+  }
+
+  #[test]
+  pub fn reports_missing_arguments() {
+    assert_eq!(diagnostics("f(, a)"), vec!["1:3: Unexpected Token"]);
+  }
+
+  #[test]
+  pub fn reports_broken_conditionals() {
+    assert_eq!(diagnostics("a ? b"), vec!["1:6: Expected :"]);
+    assert_eq!(diagnostics("if a b"), vec!["1:6: Expected LBRACE"]);
+    assert_eq!(diagnostics("if a { b"), vec!["1:9: Expected RBRACE"]);
+    assert_eq!(
+      diagnostics("if a { b c }\nd )"),
+      vec!["1:10: Unexpected Token", "2:3: Unexpected Token"]
+    );
+  }
+
+  #[test]
+  pub fn skips_unexpected_token_at_line_start() {
+    assert_eq!(
+      diagnostics("a\n)\n}\nb"),
+      vec!["2:1: Unexpected Token", "3:1: Unexpected Token"]
+    );
+  }
+
+  #[test]
+  pub fn reports_broken_loops() {
+    assert_eq!(diagnostics("for 1 in a {}"), vec!["1:5: Expected Identifier"]);
+    assert_eq!(diagnostics("for i a {}"), vec!["1:7: Expected in"]);
+    assert_eq!(diagnostics("while a b"), vec!["1:9: Expected LBRACE"]);
+    assert_eq!(diagnostics("break"), vec!["1:1: `break` outside of a loop"]);
+    assert_eq!(
+      diagnostics("while a { f = () => continue }"),
+      vec!["1:21: `continue` outside of a loop"]
+    );
+  }
+
+  #[test]
+  pub fn reports_keyword_misuse() {
+    assert_eq!(diagnostics("if = 3"), vec!["1:1: Cannot assign to keyword `if`"]);
+    assert_eq!(
+      diagnostics("a = 1\ntrue += 1"),
+      vec!["2:1: Cannot assign to keyword `true`"]
+    );
+    assert_eq!(diagnostics("let while = 1"), vec!["1:5: Expected Identifier"]);
+    assert_eq!(diagnostics("let a 1"), vec!["1:7: Expected ="]);
+    assert_eq!(diagnostics("fn for() = 1"), vec!["1:4: Expected Identifier"]);
+    assert_eq!(diagnostics("a = else"), vec!["1:5: Unexpected Token"]);
+    assert_eq!(diagnostics("return 1"), vec!["1:1: `return` outside of a function"]);
+  }
+
+  #[test]
+  pub fn reports_unterminated_block_comment() {
+    assert_eq!(diagnostics("a = 1 /* b"), vec!["1:7: Unterminated block comment"]);
+  }
+
+  #[test]
+  pub fn reports_unclosed_list() {
+    assert_eq!(diagnostics("a = [1, 2; b = 1"), vec!["1:10: Expected RBRACKET"]);
+  }
+
+  #[test]
+  pub fn reports_broken_function_definitions() {
+    assert_eq!(diagnostics("fn f x = 1"), vec!["1:6: Expected LPAREN"]);
+    assert_eq!(diagnostics("fn f(x) x"), vec!["1:9: Expected ="]);
+    assert_eq!(diagnostics("fn f(x + 1) = x"), vec!["1:6: Expected Identifier"]);
+  }
+
+  #[test]
+  pub fn recovers_at_closing_brackets() {
+    assert_eq!(
+      diagnostics("((a b) c) + (d e)"),
+      vec!["1:5: Expected RPAREN", "1:16: Expected RPAREN"]
+    );
+  }
+
+  #[test]
+  pub fn reports_code_nested_too_deeply() {
+    stack::run(|| {
+      let parens = format!("{}1{}", "(".repeat(1200), ")".repeat(1200));
+      let blocks = format!("{}1{}", "{ ".repeat(1200), " }".repeat(1200));
+      let unary = format!("{}1", "- ".repeat(1200));
+      let power = vec!["2"; 1200].join("**");
+
+      assert_eq!(
+        diagnostics(&parens),
+        vec!["1:1001: Code is nested deeper than 1000 levels"]
+      );
+      assert_eq!(
+        diagnostics(&blocks),
+        vec!["1:2001: Code is nested deeper than 1000 levels"]
+      );
+      assert_eq!(
+        diagnostics(&unary),
+        vec!["1:2001: Code is nested deeper than 1000 levels"]
+      );
+      // right associative operators nest their right operand, unlike the ones associating to the left
+      assert_eq!(
+        diagnostics(&power),
+        vec!["1:3001: Code is nested deeper than 1000 levels"]
+      );
+    });
+  }
+
+  #[test]
+  pub fn does_not_count_chains_as_nesting() {
+    stack::run(|| {
+      let sum = format!("total = {}", vec!["1"; 1000].join(" + "));
+      let (ast, diagnostics) = ast::parse(&SourceCode::new(sum.clone()));
+
+      assert_eq!(diagnostics, vec![]);
+      assert_eq!(ast.to_string(), sum);
+    });
+  }
+
+  #[test]
+  pub fn pulls_tokens_with_bounded_lookahead() {
+    let source_code = SourceCode::new("a = 1\nb += 2\nc\nd = [1, 2]".to_string());
+    let pulled = Cell::new(0);
+    let mut parser = Parser::new(Tokeniser::new(&source_code).inspect(|_| pulled.set(pulled.get() + 1)));
+    let mut lines = vec![];
+
+    while parser.current_token().is_some() {
+      parser.parse_line(&mut lines);
+
+      assert!(pulled.get() <= parser.consumed + 2);
+    }
+
+    assert_eq!(lines.len(), 4);
+    assert_eq!(format!("{}", lines[1]), "b += 2");
+  }
+
+  #[test]
+  pub fn keeps_no_start_locations_between_lines() {
+    let source_code = SourceCode::new("a = [1, (2)]\nif a { b } else { -c }\nwhile x { f(y, z) }".to_string());
+    let mut parser = Parser::new(Tokeniser::new(&source_code));
+    let mut lines = vec![];
+
+    while parser.current_token().is_some() {
+      parser.parse_line(&mut lines);
+
+      assert_eq!(parser.pstack, vec![]);
+    }
+  }
+
+  #[test]
+  pub fn parses_one_statement_at_a_time() {
+    let source_code = SourceCode::new("a = 1; b += 2 3\nfn f(x) = {\n  x\n}\n[".to_string());
+    let pulled = Cell::new(0);
+    let mut statements = ast::statements(Tokeniser::new(&source_code).inspect(|_| pulled.set(pulled.get() + 1)));
+
+    let (statement, diagnostics) = statements.next().unwrap();
+    assert_eq!((statement.to_string(), diagnostics), ("a = 1".to_string(), vec![]));
+    // the statement is parsed before the rest of the code is tokenised
+    assert!(pulled.get() <= 5);
+
+    let (statement, diagnostics) = statements.next().unwrap();
+    assert_eq!((statement.to_string(), diagnostics), ("b += 2".to_string(), vec![]));
+
+    let (_, diagnostics) = statements.next().unwrap();
+    let diagnostics: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect();
+    assert_eq!(diagnostics, vec!["1:15: Unexpected Token"]);
+
+    let (statement, diagnostics) = statements.next().unwrap();
+    assert_eq!(
+      (statement.to_string(), diagnostics),
+      ("fn f(x) = { x }".to_string(), vec![])
+    );
+
+    let (_, diagnostics) = statements.next().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert!(statements.next().is_none());
+  }
 }
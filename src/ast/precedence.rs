@@ -0,0 +1,122 @@
+//! Binding power, associativity and fixity of every operator, which drive the expression parser
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+
+use super::operator::{BinaryOp, IncrementOp, UnaryOp};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Associativity {
+  Left,
+  Right,
+}
+
+/// Where an operator stands, together with the node it builds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fixity {
+  Prefix(PrefixOp),
+  Infix(BinaryOp, Associativity),
+  Postfix(IncrementOp),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrefixOp {
+  Unary(UnaryOp),
+  Increment(IncrementOp),
+}
+
+#[derive(Debug)]
+pub struct Operator {
+  pub fixity: Fixity,
+  /// Operators with higher precedence bind tighter
+  pub precedence: u8,
+}
+
+impl Operator {
+  const fn new(fixity: Fixity, precedence: u8) -> Operator {
+    Operator { fixity, precedence }
+  }
+
+  pub fn symbol(&self) -> &'static str {
+    match self.fixity {
+      Prefix(PrefixOp::Unary(op)) => op.symbol(),
+      Prefix(PrefixOp::Increment(op)) | Postfix(op) => op.symbol(),
+      Infix(op, _) => op.symbol(),
+    }
+  }
+}
+
+use Associativity::*;
+use BinaryOp::*;
+use Fixity::*;
+use IncrementOp::*;
+use UnaryOp::*;
+
+/// Every operator of expressions, from the loosest to the tightest binding
+pub const OPERATORS: &[Operator] = &[
+  Operator::new(Infix(Pipe, Left), 1),
+  Operator::new(Infix(Or, Left), 2),
+  Operator::new(Infix(And, Left), 3),
+  Operator::new(Infix(BitOr, Left), 4),
+  Operator::new(Infix(BitXor, Left), 5),
+  Operator::new(Infix(BitAnd, Left), 6),
+  Operator::new(Infix(Eq, Left), 7),
+  Operator::new(Infix(Ne, Left), 7),
+  Operator::new(Infix(Lt, Left), 8),
+  Operator::new(Infix(Gt, Left), 8),
+  Operator::new(Infix(Le, Left), 8),
+  Operator::new(Infix(Ge, Left), 8),
+  // binds tighter than comparisons and logic, so `0..n || m` is `(0..n) || m`, but looser than arithmetic
+  Operator::new(Infix(Range, Left), 9),
+  Operator::new(Infix(Shl, Left), 10),
+  Operator::new(Infix(Shr, Left), 10),
+  Operator::new(Infix(Add, Left), 11),
+  Operator::new(Infix(Sub, Left), 11),
+  Operator::new(Infix(Mul, Left), 12),
+  Operator::new(Infix(Div, Left), 12),
+  Operator::new(Infix(Rem, Left), 12),
+  Operator::new(Prefix(PrefixOp::Unary(Plus)), 13),
+  Operator::new(Prefix(PrefixOp::Unary(Minus)), 13),
+  Operator::new(Prefix(PrefixOp::Unary(Not)), 13),
+  Operator::new(Prefix(PrefixOp::Unary(BitNot)), 13),
+  // binds tighter than the prefix operators, so `-2 ** 2` is `-(2 ** 2)`
+  Operator::new(Infix(Pow, Right), 14),
+  Operator::new(Prefix(PrefixOp::Increment(Increment)), 15),
+  Operator::new(Prefix(PrefixOp::Increment(Decrement)), 15),
+  Operator::new(Postfix(Increment), 16),
+  Operator::new(Postfix(Decrement), 16),
+];
+
+/// Every use of one symbol, with the node it builds and how strongly it binds, so the parser looks it up only once
+#[derive(Debug, Default)]
+pub struct Operators {
+  pub prefix: Option<(PrefixOp, u8)>,
+  /// How strongly the operator binds to its left and right operands.
+  /// The side the operator associates to binds a bit stronger, so that `a - b - c` is `(a - b) - c`.
+  pub infix: Option<(BinaryOp, u8, u8)>,
+  pub postfix: Option<(IncrementOp, u8)>,
+}
+
+static BY_SYMBOL: Lazy<HashMap<&'static str, Operators>> = Lazy::new(|| {
+  let mut operators: HashMap<_, Operators> = HashMap::new();
+
+  for op in OPERATORS {
+    let uses = operators.entry(op.symbol()).or_default();
+    let power = op.precedence * 2;
+
+    match op.fixity {
+      Prefix(prefix) => uses.prefix = Some((prefix, power)),
+      Infix(infix, Right) => uses.infix = Some((infix, power + 1, power)),
+      Infix(infix, Left) => uses.infix = Some((infix, power, power + 1)),
+      Postfix(postfix) => uses.postfix = Some((postfix, power)),
+    }
+  }
+
+  operators
+});
+
+/// Returns every operator written with the symbol
+pub fn find(symbol: &str) -> Option<&'static Operators> {
+  BY_SYMBOL.get(symbol)
+}
@@ -0,0 +1,278 @@
+//! Traversals over every node of the tree, for tools like linters and rewriters.
+//! Override `visit`, `visit_mut` or `fold` to handle the nodes you are interested in,
+//! and call the matching `walk` method from it to go on into the children.
+
+use std::rc::Rc;
+
+use super::ast::{Ast, Value};
+
+/// Looks at the tree without changing it
+pub trait Visitor {
+  fn visit(&mut self, ast: &Ast) {
+    self.walk(ast);
+  }
+
+  /// Visits the children of the node, from left to right as they are written in the code
+  fn walk(&mut self, ast: &Ast) {
+    match &ast.value {
+      Value::Empty
+      | Value::Literal(_)
+      | Value::Identifier(_)
+      | Value::Error(_)
+      | Value::Break
+      | Value::Continue
+      | Value::Return(None) => {}
+      Value::Unary(_, expr)
+      | Value::Expression(expr)
+      | Value::PrefixIncrement(_, expr)
+      | Value::PostfixIncrement(expr, _)
+      | Value::Return(Some(expr)) => self.visit(expr),
+      Value::Binary(left, _, right)
+      | Value::Assignment(left, right)
+      | Value::CompoundAssignment(left, _, right)
+      | Value::While(left, right)
+      | Value::Declaration(left, right) => {
+        self.visit(left);
+        self.visit(right);
+      }
+      Value::Conditional(first, second, third) | Value::For(first, second, third) => {
+        self.visit(first);
+        self.visit(second);
+        self.visit(third);
+      }
+      Value::If(condition, then, otherwise) => {
+        self.visit(condition);
+        self.visit(then);
+        if let Some(otherwise) = otherwise {
+          self.visit(otherwise);
+        }
+      }
+      Value::Call(callee, args) => {
+        self.visit(callee);
+        args.iter().for_each(|arg| self.visit(arg));
+      }
+      Value::Lambda(params, body) => {
+        params.iter().for_each(|param| self.visit(param));
+        self.visit(body);
+      }
+      Value::FunctionDefinition(name, params, body) => {
+        self.visit(name);
+        params.iter().for_each(|param| self.visit(param));
+        self.visit(body);
+      }
+      Value::List(items) | Value::Block(items) | Value::Program(items) => {
+        items.iter().for_each(|item| self.visit(item))
+      }
+    }
+  }
+}
+
+/// Changes the tree in place
+pub trait VisitorMut {
+  fn visit_mut(&mut self, ast: &mut Ast) {
+    self.walk_mut(ast);
+  }
+
+  /// Visits the children of the node, from left to right as they are written in the code
+  fn walk_mut(&mut self, ast: &mut Ast) {
+    match &mut ast.value {
+      Value::Empty
+      | Value::Literal(_)
+      | Value::Identifier(_)
+      | Value::Error(_)
+      | Value::Break
+      | Value::Continue
+      | Value::Return(None) => {}
+      Value::Unary(_, expr)
+      | Value::Expression(expr)
+      | Value::PrefixIncrement(_, expr)
+      | Value::PostfixIncrement(expr, _)
+      | Value::Return(Some(expr)) => self.visit_mut(expr),
+      Value::Binary(left, _, right)
+      | Value::Assignment(left, right)
+      | Value::CompoundAssignment(left, _, right)
+      | Value::While(left, right)
+      | Value::Declaration(left, right) => {
+        self.visit_mut(left);
+        self.visit_mut(right);
+      }
+      Value::Conditional(first, second, third) | Value::For(first, second, third) => {
+        self.visit_mut(first);
+        self.visit_mut(second);
+        self.visit_mut(third);
+      }
+      Value::If(condition, then, otherwise) => {
+        self.visit_mut(condition);
+        self.visit_mut(then);
+        if let Some(otherwise) = otherwise {
+          self.visit_mut(otherwise);
+        }
+      }
+      Value::Call(callee, args) => {
+        self.visit_mut(callee);
+        args.iter_mut().for_each(|arg| self.visit_mut(arg));
+      }
+      Value::Lambda(params, body) => {
+        params.iter_mut().for_each(|param| self.visit_mut(param));
+        self.visit_mut(Rc::make_mut(body));
+      }
+      Value::FunctionDefinition(name, params, body) => {
+        self.visit_mut(name);
+        params.iter_mut().for_each(|param| self.visit_mut(param));
+        self.visit_mut(Rc::make_mut(body));
+      }
+      Value::List(items) | Value::Block(items) | Value::Program(items) => {
+        items.iter_mut().for_each(|item| self.visit_mut(item))
+      }
+    }
+  }
+}
+
+/// Builds a new tree out of the old one, which can replace nodes with nodes of another kind
+pub trait Fold {
+  fn fold(&mut self, ast: Ast) -> Ast {
+    self.walk_fold(ast)
+  }
+
+  /// Folds the children of the node, keeping the node itself and its location
+  fn walk_fold(&mut self, ast: Ast) -> Ast {
+    let mut fold = |ast: Box<Ast>| Box::new(self.fold(*ast));
+
+    let value = match ast.value {
+      value @ (Value::Empty
+      | Value::Literal(_)
+      | Value::Identifier(_)
+      | Value::Error(_)
+      | Value::Break
+      | Value::Continue
+      | Value::Return(None)) => value,
+      Value::Unary(op, expr) => Value::Unary(op, fold(expr)),
+      Value::Expression(expr) => Value::Expression(fold(expr)),
+      Value::PrefixIncrement(op, id) => Value::PrefixIncrement(op, fold(id)),
+      Value::PostfixIncrement(id, op) => Value::PostfixIncrement(fold(id), op),
+      Value::Return(Some(expr)) => Value::Return(Some(fold(expr))),
+      Value::Binary(left, op, right) => Value::Binary(fold(left), op, fold(right)),
+      Value::Assignment(id, expr) => Value::Assignment(fold(id), fold(expr)),
+      Value::CompoundAssignment(id, op, expr) => Value::CompoundAssignment(fold(id), op, fold(expr)),
+      Value::While(condition, body) => Value::While(fold(condition), fold(body)),
+      Value::Declaration(id, expr) => Value::Declaration(fold(id), fold(expr)),
+      Value::Conditional(condition, then, otherwise) => {
+        Value::Conditional(fold(condition), fold(then), fold(otherwise))
+      }
+      Value::For(id, iterable, body) => Value::For(fold(id), fold(iterable), fold(body)),
+      Value::If(condition, then, otherwise) => Value::If(fold(condition), fold(then), otherwise.map(fold)),
+      Value::Call(callee, args) => {
+        let callee = fold(callee);
+        Value::Call(callee, self.fold_all(args))
+      }
+      Value::Lambda(params, body) => {
+        let params = self.fold_all(params);
+        Value::Lambda(params, Rc::new(self.fold(Rc::unwrap_or_clone(body))))
+      }
+      Value::FunctionDefinition(name, params, body) => {
+        let name = Box::new(self.fold(*name));
+        let params = self.fold_all(params);
+        Value::FunctionDefinition(name, params, Rc::new(self.fold(Rc::unwrap_or_clone(body))))
+      }
+      Value::List(items) => Value::List(self.fold_all(items)),
+      Value::Block(lines) => Value::Block(self.fold_all(lines)),
+      Value::Program(lines) => Value::Program(self.fold_all(lines)),
+    };
+
+    Ast::from_value(value, ast.start, ast.end)
+  }
+
+  /// Folds every item of a list, like the arguments of a call or the lines of a block
+  fn fold_all(&mut self, items: Vec<Ast>) -> Vec<Ast> {
+    items.into_iter().map(|item| self.fold(item)).collect()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{Fold, Visitor, VisitorMut};
+  use crate::ast::{self, Ast, BinaryOp, Literal, Value};
+
+  struct Identifiers(Vec<String>);
+
+  impl Visitor for Identifiers {
+    fn visit(&mut self, ast: &Ast) {
+      if let Value::Identifier(id) = &ast.value {
+        self.0.push(id.clone());
+      }
+
+      self.walk(ast);
+    }
+  }
+
+  struct Rename(&'static str, &'static str);
+
+  impl VisitorMut for Rename {
+    fn visit_mut(&mut self, ast: &mut Ast) {
+      match &mut ast.value {
+        Value::Identifier(id) if id == self.0 => *id = self.1.to_string(),
+        _ => self.walk_mut(ast),
+      }
+    }
+  }
+
+  /// Adds and multiplies integer literals, like an optimiser would
+  struct Constants;
+
+  impl Fold for Constants {
+    fn fold(&mut self, ast: Ast) -> Ast {
+      let ast = self.walk_fold(ast);
+
+      let value = match &ast.value {
+        Value::Binary(left, op, right) => match (&left.value, op, &right.value) {
+          (Value::Literal(Literal::Int(left)), BinaryOp::Add, Value::Literal(Literal::Int(right))) => {
+            left.checked_add(*right)
+          }
+          (Value::Literal(Literal::Int(left)), BinaryOp::Mul, Value::Literal(Literal::Int(right))) => {
+            left.checked_mul(*right)
+          }
+          _ => None,
+        },
+        Value::Expression(expr) => match expr.value {
+          Value::Literal(Literal::Int(i)) => Some(i),
+          _ => None,
+        },
+        _ => None,
+      };
+
+      match value {
+        Some(i) => Ast::from_value(Value::Literal(Literal::Int(i)), ast.start, ast.end),
+        None => ast,
+      }
+    }
+  }
+
+  const CODE: &str =
+    "fn f(a, b) = { let c = [a, -b]; for i in c { if i > 0 { g(i); n++ } else { return h } } }; x = f(1, y)";
+
+  #[test]
+  pub fn visits_every_node_in_order() {
+    let mut identifiers = Identifiers(vec![]);
+    identifiers.visit(&ast::from_string(CODE));
+
+    assert_eq!(
+      identifiers.0,
+      vec!["f", "a", "b", "c", "a", "b", "i", "c", "i", "g", "i", "n", "h", "x", "f", "y"]
+    );
+  }
+
+  #[test]
+  pub fn changes_nodes_in_place() {
+    let mut ast = ast::from_string("i = 0; while i < n { i += step(i) }");
+    Rename("i", "j").visit_mut(&mut ast);
+
+    assert_eq!(ast.to_string(), "j = 0; while j < n { j += step(j) }");
+  }
+
+  #[test]
+  pub fn folds_nodes_into_other_kinds() {
+    let ast = Constants.fold(ast::from_string("x = 2 * (3 + 4) + y; f(1 + 1, [2 * 2])"));
+
+    assert_eq!(ast.to_string(), "x = 14 + y; f(2, [4])");
+  }
+}
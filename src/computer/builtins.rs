@@ -0,0 +1,222 @@
+use std::{collections::HashMap, fmt::Display};
+
+use once_cell::sync::Lazy;
+
+use crate::ast::Ast;
+
+use super::{
+  error::{ErrorKind, RuntimeError},
+  value::Value,
+  Computer,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+  Exact(usize),
+  AtLeast(usize),
+}
+
+#[derive(Debug)]
+pub enum Implementation {
+  Pure(fn(&[Value]) -> Result<Value, ErrorKind>),
+  /// Builtin calling functions passed to it, which needs the computer to run them
+  HigherOrder(fn(&mut Computer, &[Value], &Ast) -> Result<Value, RuntimeError>),
+}
+
+#[derive(Debug)]
+pub struct Builtin {
+  pub name: &'static str,
+  pub arity: Arity,
+  pub function: Implementation,
+}
+
+/// Builtins are only equal to themselves
+impl PartialEq for Builtin {
+  fn eq(&self, other: &Self) -> bool {
+    std::ptr::eq(self, other)
+  }
+}
+
+impl Display for Arity {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let (prefix, count) = match self {
+      Arity::Exact(count) => ("", *count),
+      Arity::AtLeast(count) => ("at least ", *count),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+
+    write!(f, "{}{} argument{}", prefix, count, plural)
+  }
+}
+
+impl Arity {
+  pub fn accepts(&self, count: usize) -> bool {
+    match self {
+      Arity::Exact(expected) => count == *expected,
+      Arity::AtLeast(expected) => count >= *expected,
+    }
+  }
+}
+
+fn number(name: &str, value: &Value) -> Result<f64, ErrorKind> {
+  match value {
+    Value::Int(i) => Ok(*i as f64),
+    Value::Float(f) => Ok(*f),
+    value => Err(ErrorKind::InvalidArgument(name.to_string(), value.type_name())),
+  }
+}
+
+/// Builtin taking a single number and always returning a float
+macro_rules! float_function {
+  ($name: literal, $function: expr) => {
+    Builtin {
+      name: $name,
+      arity: Arity::Exact(1),
+      function: Implementation::Pure(|args| Ok(Value::Float($function(number($name, &args[0])?)))),
+    }
+  };
+}
+
+/// Builtin taking a single number, which keeps integers as they are
+macro_rules! rounding_function {
+  ($name: literal, $function: expr) => {
+    Builtin {
+      name: $name,
+      arity: Arity::Exact(1),
+      function: Implementation::Pure(|args| match &args[0] {
+        Value::Int(i) => Ok(Value::Int(*i)),
+        value => Ok(Value::Float($function(number($name, value)?))),
+      }),
+    }
+  };
+}
+
+fn abs(args: &[Value]) -> Result<Value, ErrorKind> {
+  match &args[0] {
+    Value::Int(i) => i.checked_abs().map(Value::Int).ok_or(ErrorKind::IntegerOverflow),
+    value => Ok(Value::Float(number("abs", value)?.abs())),
+  }
+}
+
+fn pow(args: &[Value]) -> Result<Value, ErrorKind> {
+  match (&args[0], &args[1]) {
+    (Value::Int(base), Value::Int(exponent)) if *exponent >= 0 => u32::try_from(*exponent)
+      .ok()
+      .and_then(|exponent| base.checked_pow(exponent))
+      .map(Value::Int)
+      .ok_or(ErrorKind::IntegerOverflow),
+    (base, exponent) => Ok(Value::Float(number("pow", base)?.powf(number("pow", exponent)?))),
+  }
+}
+
+/// Picks the argument which `pick` prefers, keeping the result an integer if all arguments are integers.
+/// NaN is picked over any other argument, so the result does not depend on the order of the arguments.
+fn select(name: &str, args: &[Value], pick: fn(f64, f64) -> bool) -> Result<Value, ErrorKind> {
+  let mut result = &args[0];
+  let mut result_number = number(name, result)?;
+  let mut is_int = matches!(result, Value::Int(_));
+
+  for arg in &args[1..] {
+    let arg_number = number(name, arg)?;
+    is_int &= matches!(arg, Value::Int(_));
+
+    if arg_number.is_nan() || pick(arg_number, result_number) {
+      result = arg;
+      result_number = arg_number;
+    }
+  }
+
+  match result {
+    Value::Int(i) if is_int => Ok(Value::Int(*i)),
+    _ => Ok(Value::Float(result_number)),
+  }
+}
+
+fn list<'a>(name: &str, value: &'a Value, ast: &Ast) -> Result<&'a [Value], RuntimeError> {
+  match value {
+    Value::List(items, _) => Ok(items),
+    value => Err(RuntimeError::at(
+      ErrorKind::InvalidArgument(name.to_string(), value.type_name()),
+      ast,
+    )),
+  }
+}
+
+fn map(computer: &mut Computer, args: &[Value], ast: &Ast) -> Result<Value, RuntimeError> {
+  let items = list("map", &args[1], ast)?
+    .iter()
+    .map(|item| computer.call(&args[0], vec![item.clone()], ast))
+    .collect::<Result<_, _>>()?;
+
+  Value::list(items).map_err(|kind| RuntimeError::at(kind, ast))
+}
+
+fn filter(computer: &mut Computer, args: &[Value], ast: &Ast) -> Result<Value, RuntimeError> {
+  let mut items = vec![];
+
+  for item in list("filter", &args[1], ast)? {
+    match computer.call(&args[0], vec![item.clone()], ast)? {
+      Value::Bool(true) => items.push(item.clone()),
+      Value::Bool(false) => {}
+      value => {
+        return Err(RuntimeError::at(
+          ErrorKind::InvalidArgument("filter".to_string(), value.type_name()),
+          ast,
+        ))
+      }
+    }
+  }
+
+  Value::list(items).map_err(|kind| RuntimeError::at(kind, ast))
+}
+
+pub static BUILTINS: Lazy<HashMap<&'static str, Builtin>> = Lazy::new(|| {
+  [
+    float_function!("sqrt", f64::sqrt),
+    float_function!("exp", f64::exp),
+    float_function!("ln", f64::ln),
+    float_function!("log10", f64::log10),
+    float_function!("sin", f64::sin),
+    float_function!("cos", f64::cos),
+    float_function!("tan", f64::tan),
+    float_function!("asin", f64::asin),
+    float_function!("acos", f64::acos),
+    float_function!("atan", f64::atan),
+    rounding_function!("floor", f64::floor),
+    rounding_function!("ceil", f64::ceil),
+    rounding_function!("round", f64::round),
+    Builtin {
+      name: "abs",
+      arity: Arity::Exact(1),
+      function: Implementation::Pure(abs),
+    },
+    Builtin {
+      name: "pow",
+      arity: Arity::Exact(2),
+      function: Implementation::Pure(pow),
+    },
+    Builtin {
+      name: "min",
+      arity: Arity::AtLeast(1),
+      function: Implementation::Pure(|args| select("min", args, |arg, result| arg < result)),
+    },
+    Builtin {
+      name: "max",
+      arity: Arity::AtLeast(1),
+      function: Implementation::Pure(|args| select("max", args, |arg, result| arg > result)),
+    },
+    Builtin {
+      name: "map",
+      arity: Arity::Exact(2),
+      function: Implementation::HigherOrder(map),
+    },
+    Builtin {
+      name: "filter",
+      arity: Arity::Exact(2),
+      function: Implementation::HigherOrder(filter),
+    },
+  ]
+  .into_iter()
+  .map(|builtin| (builtin.name, builtin))
+  .collect()
+});
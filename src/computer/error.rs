@@ -0,0 +1,101 @@
+use std::fmt::Display;
+
+use super::{builtins::Arity, value::Value};
+use crate::{
+  ast::{self, Ast},
+  source_code::{Location, SourceMap},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+  EmptyValue,
+  Syntax(ast::ErrorCode),
+  VariableNotFound(String),
+  IntLiteralTooBig(u128),
+  IntegerOverflow,
+  DivisionByZero,
+  ShiftOutOfRange(i128),
+  UnsupportedOperand(String, &'static str),
+  UnsupportedOperands(String, &'static str, &'static str),
+  NotCallable(&'static str),
+  RecursionLimitExceeded(usize),
+  NestedTooDeeply(usize),
+  UnknownFunction(String),
+  WrongArgumentCount(String, Arity, usize),
+  InvalidArgument(String, &'static str),
+  InvalidCondition(&'static str),
+  NotIterable(&'static str),
+  IterationLimitExceeded(usize),
+  ListNestedTooDeeply(usize),
+  Break,
+  Continue,
+  Return(Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+  /// Boxed to keep results small, since every step of the computation passes them up
+  pub kind: Box<ErrorKind>,
+  pub start: Location,
+  pub end: Location,
+}
+
+impl Display for ErrorKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ErrorKind::EmptyValue => write!(f, "cannot compute empty value"),
+      ErrorKind::Syntax(code) => write!(f, "{}", code),
+      ErrorKind::VariableNotFound(id) => write!(f, "variable `{}` not found", id),
+      ErrorKind::IntLiteralTooBig(i) => write!(f, "{} does not fit into a 128-bit signed integer", i),
+      ErrorKind::IntegerOverflow => write!(f, "integer overflow"),
+      ErrorKind::DivisionByZero => write!(f, "division by zero"),
+      ErrorKind::ShiftOutOfRange(shift) => write!(f, "shift by {} is out of range 0..{}", shift, i128::BITS),
+      ErrorKind::UnsupportedOperand(op, operand) => write!(f, "cannot apply `{}` to {}", op, operand),
+      ErrorKind::UnsupportedOperands(op, left, right) => {
+        write!(f, "cannot apply `{}` to {} and {}", op, left, right)
+      }
+      ErrorKind::NotCallable(value) => write!(f, "{} cannot be called", value),
+      ErrorKind::RecursionLimitExceeded(limit) => write!(f, "recursion limit of {} calls exceeded", limit),
+      ErrorKind::NestedTooDeeply(limit) => write!(f, "computation is nested deeper than {} levels", limit),
+      ErrorKind::UnknownFunction(name) => write!(f, "function `{}` not found", name),
+      ErrorKind::WrongArgumentCount(name, arity, count) => {
+        write!(f, "`{}` expects {}, got {}", name, arity, count)
+      }
+      ErrorKind::InvalidArgument(name, argument) => write!(f, "`{}` cannot be applied to {}", name, argument),
+      ErrorKind::InvalidCondition(value) => write!(f, "condition must be bool, got {}", value),
+      ErrorKind::NotIterable(value) => write!(f, "cannot iterate over {}", value),
+      ErrorKind::IterationLimitExceeded(limit) => write!(f, "loop exceeded the limit of {} iterations", limit),
+      ErrorKind::ListNestedTooDeeply(limit) => write!(f, "lists are nested deeper than {} levels", limit),
+      // loops and functions stop these, so they can only be seen if there is none around
+      ErrorKind::Break => write!(f, "`break` outside of a loop"),
+      ErrorKind::Continue => write!(f, "`continue` outside of a loop"),
+      ErrorKind::Return(_) => write!(f, "`return` outside of a function"),
+    }
+  }
+}
+
+/// Shows only the line and column, as the file can only be named by the source map, see `describe`
+impl Display for RuntimeError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}: {}", self.start, self.kind)
+  }
+}
+
+impl RuntimeError {
+  pub fn new(kind: ErrorKind, start: Location, end: Location) -> RuntimeError {
+    RuntimeError {
+      kind: Box::new(kind),
+      start,
+      end,
+    }
+  }
+
+  pub fn at(kind: ErrorKind, ast: &Ast) -> RuntimeError {
+    RuntimeError::new(kind, ast.start(), ast.end())
+  }
+
+  /// Describes the error as `file:line:col: message`, taking the name of the file from the source map
+  pub fn describe(&self, source_map: &SourceMap) -> String {
+    source_map.describe(self.start, &self.kind)
+  }
+}
@@ -1,80 +1,473 @@
-use std::collections::HashMap;
+#[cfg(test)]
+mod test;
 
-use crate::ast::{Ast, Literal, Value};
+mod builtins;
+mod error;
+mod operators;
+mod scope;
+mod value;
+
+use std::rc::Rc;
+
+use crate::{
+  ast::{self, Ast, BinaryOp, IncrementOp, Literal, UnaryOp},
+  stack::MAX_NESTING,
+};
+
+use self::{
+  builtins::{Arity, Implementation, BUILTINS},
+  scope::{Scope, ScopeRef},
+  value::{Closure, Function},
+};
+
+pub use error::{ErrorKind, RuntimeError};
+pub use value::Value;
+
+const DEFAULT_RECURSION_LIMIT: usize = 100;
+const DEFAULT_ITERATION_LIMIT: usize = 1_000_000;
 
 pub struct Computer {
-  context: HashMap<String, f64>,
+  globals: ScopeRef,
+  scope: ScopeRef,
+  depth: usize,
+  /// Nesting of computations, which includes the ones of every function being called
+  nesting: usize,
+  recursion_limit: usize,
+  iteration_limit: usize,
+}
+
+impl Default for Computer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Functions stored in the globals keep them alive through the scopes they captured, so they are emptied to free both
+impl Drop for Computer {
+  fn drop(&mut self) {
+    self.globals.borrow_mut().clear();
+  }
 }
 
 impl Computer {
   pub fn new() -> Self {
+    let globals = Scope::new(None);
+
     Computer {
-      context: HashMap::new(),
+      scope: globals.clone(),
+      globals,
+      depth: 0,
+      nesting: 0,
+      recursion_limit: DEFAULT_RECURSION_LIMIT,
+      iteration_limit: DEFAULT_ITERATION_LIMIT,
     }
   }
 
-  pub fn iter(&self) -> std::collections::hash_map::Iter<'_, String, f64> {
-    self.context.iter()
+  /// Sets how deep user-defined functions may call each other before the computation fails
+  pub fn set_recursion_limit(&mut self, limit: usize) {
+    self.recursion_limit = limit;
+  }
+
+  /// Sets how many times a single loop may repeat before the computation fails
+  pub fn set_iteration_limit(&mut self, limit: usize) {
+    self.iteration_limit = limit;
+  }
+
+  /// Returns global variables sorted by name
+  pub fn variables(&self) -> Vec<(String, Value)> {
+    self.globals.borrow().variables()
   }
 
-  pub fn compute(&mut self, ast: &Ast) -> Result<f64, String> {
+  fn identifier(ast: &Ast) -> Result<&String, RuntimeError> {
     match &ast.value {
-      Value::Empty => Err(format!("Cannot compute empty value")),
-      Value::Literal(l) => match l {
-        Literal::Char(_) => Err(format!("Char literals are currently not supported")),
-        Literal::Int(i) => {
-          if let Ok(u) = i32::try_from(*i).and_then(|i| Ok(f64::from(i))) {
-            Ok(u)
-          } else {
-            Err(format!("Error: {} is too big", i))
-          }
-        }
-        Literal::Float(f) => Ok(*f),
-        Literal::String(_) => Err(format!("String literals are currently not supported")),
+      ast::Value::Identifier(id) => Ok(id),
+      _ => Err(RuntimeError::at(
+        ErrorKind::Syntax(ast::ErrorCode::IdentifierExpected),
+        ast,
+      )),
+    }
+  }
+
+  fn lookup(&self, ast: &Ast) -> Result<(String, Value), RuntimeError> {
+    let id = Self::identifier(ast)?;
+
+    let value = self.scope.borrow().get(id);
+
+    match value.or_else(|| BUILTINS.get(id.as_str()).map(Value::Builtin)) {
+      Some(value) => Ok((id.clone(), value)),
+      None => Err(RuntimeError::at(ErrorKind::VariableNotFound(id.clone()), ast)),
+    }
+  }
+
+  /// Applies `op` to the value of the variable and stores the result, returning both old and new values
+  fn update(&mut self, id: &Ast, op: BinaryOp, operand: Value, ast: &Ast) -> Result<(Value, Value), RuntimeError> {
+    let (id, old) = self.lookup(id)?;
+    let new = operators::binary(old.clone(), op, operand).map_err(|kind| RuntimeError::at(kind, ast))?;
+
+    self.scope.borrow_mut().assign(id, new.clone());
+    Ok((old, new))
+  }
+
+  /// Calls a function or a builtin with already computed arguments
+  fn call(&mut self, callee: &Value, args: Vec<Value>, ast: &Ast) -> Result<Value, RuntimeError> {
+    let error = |kind| RuntimeError::at(kind, ast);
+
+    let (name, arity) = match callee {
+      Value::Function(function) => (function.name(), Arity::Exact(function.params.len())),
+      Value::Builtin(builtin) => (builtin.name, builtin.arity),
+      value => return Err(error(ErrorKind::NotCallable(value.type_name()))),
+    };
+
+    if !arity.accepts(args.len()) {
+      return Err(error(ErrorKind::WrongArgumentCount(
+        name.to_string(),
+        arity,
+        args.len(),
+      )));
+    }
+
+    match callee {
+      Value::Builtin(builtin) => match builtin.function {
+        Implementation::Pure(function) => function(&args).map_err(error),
+        Implementation::HigherOrder(function) => function(self, &args, ast),
       },
-      Value::Identifier(id) => match self.context.get(id) {
-        Some(value) => Ok(*value),
-        None => Err(format!("{}: variable not found", id)),
+      Value::Function(function) => self.call_function(function, args, ast),
+      value => Err(error(ErrorKind::NotCallable(value.type_name()))),
+    }
+  }
+
+  fn call_function(&mut self, function: &Function, args: Vec<Value>, ast: &Ast) -> Result<Value, RuntimeError> {
+    if self.depth >= self.recursion_limit {
+      return Err(RuntimeError::at(
+        ErrorKind::RecursionLimitExceeded(self.recursion_limit),
+        ast,
+      ));
+    }
+
+    let scope = Scope::new(Some(function.scope()));
+    for (param, arg) in function.params.iter().zip(args) {
+      scope.borrow_mut().define(param.clone(), arg);
+    }
+
+    self.depth += 1;
+    let result = self.in_scope(scope, &function.body);
+    self.depth -= 1;
+
+    match result {
+      Err(error) => match *error.kind {
+        ErrorKind::Return(value) => Ok(value),
+        kind => Err(RuntimeError {
+          kind: Box::new(kind),
+          ..error
+        }),
       },
-      Value::Unary(op, expr) => {
-        let value = self.compute(expr)?;
-
-        match op.as_str() {
-          "+" => Ok(value),
-          "-" => Ok(-value),
-          other => Err(format!("{}: unknown operator", other)),
-        }
-      }
-      Value::Binary(left, op, right) => {
-        let left = self.compute(left)?;
-        let right = self.compute(right)?;
-
-        match op.as_str() {
-          "+" => Ok(left + right),
-          "-" => Ok(left - right),
-          "*" => Ok(left * right),
-          "/" => Ok(left / right),
-          "%" => Ok(left % right),
-          other => Err(format!("{}: unknown operator", other)),
-        }
+      result => result,
+    }
+  }
+
+  fn in_scope(&mut self, scope: ScopeRef, ast: &Ast) -> Result<Value, RuntimeError> {
+    let outer_scope = std::mem::replace(&mut self.scope, scope);
+    let result = self.compute(ast);
+    self.scope = outer_scope;
+
+    result
+  }
+
+  /// Repeats the loop body in every scope `next` returns, stopping on `break` or the iteration limit
+  fn repeat(
+    &mut self,
+    body: &Ast,
+    ast: &Ast,
+    mut next: impl FnMut(&mut Self) -> Result<Option<ScopeRef>, RuntimeError>,
+  ) -> Result<Value, RuntimeError> {
+    let mut iterations = 0;
+
+    while let Some(scope) = next(self)? {
+      if iterations == self.iteration_limit {
+        return Err(RuntimeError::at(
+          ErrorKind::IterationLimitExceeded(self.iteration_limit),
+          ast,
+        ));
       }
-      Value::Error(e) => Err(format!("{}", e)),
-      Value::Expression(e) => self.compute(e),
-      Value::Assignment(id, expr) => {
-        let value = self.compute(&expr);
-        let id = match &id.value {
-          Value::Identifier(id) => id.clone(),
-          _ => panic!("Should be assignment"),
-        };
-
-        match value {
-          Ok(value) => {
-            self.context.insert(id, value);
-            Ok(value)
-          }
-          error => error,
-        }
+      iterations += 1;
+
+      match self.in_scope(scope, body) {
+        Ok(_) => {}
+        Err(error) => match *error.kind {
+          ErrorKind::Continue => {}
+          ErrorKind::Break => break,
+          _ => return Err(error),
+        },
       }
     }
+
+    Ok(Value::Unit)
+  }
+
+  /// Creates a function capturing the current scope
+  fn function(&self, name: Option<&Ast>, params: &[Ast], body: &Rc<Ast>) -> Result<Value, RuntimeError> {
+    let name = match name {
+      Some(name) => Some(Self::identifier(name)?.clone()),
+      None => None,
+    };
+    let params = params.iter().map(|param| Self::identifier(param).cloned()).collect::<Result<_, _>>()?;
+    let body = body.clone();
+    let closure = Closure::Strong(self.scope.clone());
+
+    Ok(Value::Function(Rc::new(Function {
+      name,
+      params,
+      body,
+      closure,
+    })))
+  }
+
+  fn condition(&mut self, condition: &Ast) -> Result<bool, RuntimeError> {
+    match self.compute(condition)? {
+      Value::Bool(b) => Ok(b),
+      value => Err(RuntimeError::at(
+        ErrorKind::InvalidCondition(value.type_name()),
+        condition,
+      )),
+    }
+  }
+
+  /// Computes lines one by one, returning the value of the last one
+  fn lines(&mut self, lines: &[Ast]) -> Result<Value, RuntimeError> {
+    let mut result = Value::Unit;
+
+    for line in lines {
+      result = self.compute(line)?;
+    }
+
+    Ok(result)
+  }
+
+  /// Makes the error of nodes that always fail, some of which are stopped by loops and functions
+  fn signal(ast: &Ast) -> RuntimeError {
+    let kind = match &ast.value {
+      ast::Value::Error(e) => ErrorKind::Syntax(e.clone()),
+      ast::Value::Break => ErrorKind::Break,
+      ast::Value::Continue => ErrorKind::Continue,
+      ast::Value::Return(None) => ErrorKind::Return(Value::Unit),
+      _ => ErrorKind::EmptyValue,
+    };
+
+    RuntimeError::at(kind, ast)
+  }
+
+  fn literal(literal: &Literal, ast: &Ast) -> Result<Value, RuntimeError> {
+    Self::literal_value(literal).map_err(|kind| RuntimeError::at(kind, ast))
+  }
+
+  fn literal_value(literal: &Literal) -> Result<Value, ErrorKind> {
+    match literal {
+      Literal::Char(c) => Ok(Value::Char(*c)),
+      Literal::Int(i) => match i128::try_from(*i) {
+        Ok(i) => Ok(Value::Int(i)),
+        Err(_) => Err(ErrorKind::IntLiteralTooBig(*i)),
+      },
+      Literal::Float(f) => Ok(Value::Float(*f)),
+      Literal::String(s) => Ok(Value::String(s.clone())),
+      Literal::Bool(b) => Ok(Value::Bool(*b)),
+      Literal::Null => Ok(Value::Unit),
+    }
+  }
+
+  fn variable(&self, ast: &Ast) -> Result<Value, RuntimeError> {
+    self.lookup(ast).map(|(_, value)| value)
+  }
+
+  fn unary(&mut self, op: UnaryOp, expr: &Ast, ast: &Ast) -> Result<Value, RuntimeError> {
+    // i128::MIN has no positive counterpart, so it can only be written as a negated literal
+    if op == UnaryOp::Minus
+      && matches!(expr.value, ast::Value::Literal(Literal::Int(i)) if i == i128::MIN.unsigned_abs())
+    {
+      return Ok(Value::Int(i128::MIN));
+    }
+
+    let value = self.compute(expr)?;
+
+    operators::unary(op, value).map_err(|kind| RuntimeError::at(kind, ast))
+  }
+
+  fn binary(&mut self, left: &Ast, op: BinaryOp, right: &Ast, ast: &Ast) -> Result<Value, RuntimeError> {
+    let left = self.compute(left)?;
+    let right = self.compute(right)?;
+
+    operators::binary(left, op, right).map_err(|kind| RuntimeError::at(kind, ast))
+  }
+
+  /// Computes the value and stores it in the variable with `store`, which either defines or assigns it
+  fn store(&mut self, id: &Ast, expr: &Ast, store: fn(&mut Scope, String, Value)) -> Result<Value, RuntimeError> {
+    let value = self.compute(expr)?;
+
+    self.assign(id, value, store)
+  }
+
+  fn compound_assignment(&mut self, id: &Ast, op: BinaryOp, expr: &Ast, ast: &Ast) -> Result<Value, RuntimeError> {
+    let value = self.compute(expr)?;
+
+    self.update(id, op, value, ast).map(|(_, new)| new)
+  }
+
+  fn increment(&mut self, id: &Ast, op: IncrementOp, ast: &Ast) -> Result<(Value, Value), RuntimeError> {
+    self.update(id, op.binary(), Value::Int(1), ast)
+  }
+
+  fn return_value(&mut self, expr: &Ast, ast: &Ast) -> Result<Value, RuntimeError> {
+    let value = self.compute(expr)?;
+
+    Err(RuntimeError::at(ErrorKind::Return(value), ast))
+  }
+
+  fn list(&mut self, items: &[Ast], ast: &Ast) -> Result<Value, RuntimeError> {
+    let items = items.iter().map(|item| self.compute(item)).collect::<Result<_, _>>()?;
+
+    Value::list(items).map_err(|kind| RuntimeError::at(kind, ast))
+  }
+
+  fn function_definition(&mut self, name: &Ast, params: &[Ast], body: &Rc<Ast>) -> Result<Value, RuntimeError> {
+    let function = self.function(Some(name), params, body)?;
+
+    self.assign(name, function, Scope::assign)
+  }
+
+  /// Stores the value of the variable with `store`, which either defines or assigns it
+  fn assign(&mut self, id: &Ast, value: Value, store: fn(&mut Scope, String, Value)) -> Result<Value, RuntimeError> {
+    let id = Self::identifier(id)?;
+
+    store(&mut self.scope.borrow_mut(), id.clone(), value.clone());
+    Ok(value)
+  }
+
+  /// Computes only the branch chosen by the condition
+  fn branch(&mut self, condition: &Ast, then: &Ast, otherwise: Option<&Ast>) -> Result<Value, RuntimeError> {
+    match (self.condition(condition)?, otherwise) {
+      (true, _) => self.compute(then),
+      (false, Some(otherwise)) => self.compute(otherwise),
+      (false, None) => Ok(Value::Unit),
+    }
+  }
+
+  fn logical(&mut self, left: &Ast, op: BinaryOp, right: &Ast) -> Result<Value, RuntimeError> {
+    let operand = |computer: &mut Computer, ast: &Ast| match computer.compute(ast)? {
+      Value::Bool(b) => Ok(b),
+      value => Err(RuntimeError::at(
+        ErrorKind::UnsupportedOperand(op.to_string(), value.type_name()),
+        ast,
+      )),
+    };
+
+    // the right operand is only evaluated when the left one does not decide the result
+    let result = match operand(self, left)? {
+      true if op == BinaryOp::Or => true,
+      false if op == BinaryOp::And => false,
+      _ => operand(self, right)?,
+    };
+
+    Ok(Value::Bool(result))
+  }
+
+  /// Passes the value on the left as the only argument to the function on the right, so `x |> f` is `f(x)`
+  fn pipe(&mut self, value: &Ast, function: &Ast, ast: &Ast) -> Result<Value, RuntimeError> {
+    let value = self.compute(value)?;
+    let function = self.compute(function)?;
+
+    self.call(&function, vec![value], ast)
+  }
+
+  fn call_expression(&mut self, callee: &Ast, args: &[Ast], ast: &Ast) -> Result<Value, RuntimeError> {
+    let function = match self.compute(callee) {
+      Err(error) => match *error.kind {
+        ErrorKind::VariableNotFound(name) => return Err(RuntimeError::at(ErrorKind::UnknownFunction(name), callee)),
+        _ => return Err(error),
+      },
+      Ok(function) => function,
+    };
+    let args = args.iter().map(|arg| self.compute(arg)).collect::<Result<Vec<_>, _>>()?;
+
+    self.call(&function, args, ast)
+  }
+
+  fn block(&mut self, lines: &[Ast]) -> Result<Value, RuntimeError> {
+    let scope = Scope::new(Some(self.scope.clone()));
+    let outer_scope = std::mem::replace(&mut self.scope, scope);
+    let result = self.lines(lines);
+    self.scope = outer_scope;
+
+    result
+  }
+
+  fn while_loop(&mut self, condition: &Ast, body: &Ast, ast: &Ast) -> Result<Value, RuntimeError> {
+    self.repeat(body, ast, |computer| {
+      Ok(computer.condition(condition)?.then(|| computer.scope.clone()))
+    })
+  }
+
+  fn for_loop(&mut self, id: &Ast, iterable: &Ast, body: &Ast, ast: &Ast) -> Result<Value, RuntimeError> {
+    let id = Self::identifier(id)?;
+    let mut items: Box<dyn Iterator<Item = Value>> = match self.compute(iterable)? {
+      Value::Range(start, end) => Box::new((start..end).map(Value::Int)),
+      Value::List(items, _) => Box::new(items.as_ref().clone().into_iter()),
+      value => return Err(RuntimeError::at(ErrorKind::NotIterable(value.type_name()), iterable)),
+    };
+
+    self.repeat(body, ast, |computer| {
+      let scope = items.next().map(|item| {
+        let scope = Scope::new(Some(computer.scope.clone()));
+        scope.borrow_mut().define(id.clone(), item);
+
+        scope
+      });
+
+      Ok(scope)
+    })
+  }
+
+  /// Computes the code, which needs a thread with `stack::STACK_SIZE` of stack, like the one `stack::run` starts
+  pub fn compute(&mut self, ast: &Ast) -> Result<Value, RuntimeError> {
+    if self.nesting == MAX_NESTING {
+      return Err(RuntimeError::at(ErrorKind::NestedTooDeeply(MAX_NESTING), ast));
+    }
+
+    self.nesting += 1;
+    let result = self.evaluate(ast);
+    self.nesting -= 1;
+
+    result
+  }
+
+  fn evaluate(&mut self, ast: &Ast) -> Result<Value, RuntimeError> {
+    match &ast.value {
+      ast::Value::Empty
+      | ast::Value::Error(_)
+      | ast::Value::Break
+      | ast::Value::Continue
+      | ast::Value::Return(None) => Err(Self::signal(ast)),
+      ast::Value::Literal(literal) => Self::literal(literal, ast),
+      ast::Value::Identifier(_) => self.variable(ast),
+      ast::Value::Unary(op, expr) => self.unary(*op, expr, ast),
+      ast::Value::Binary(left, op @ (BinaryOp::And | BinaryOp::Or), right) => self.logical(left, *op, right),
+      ast::Value::Binary(left, BinaryOp::Pipe, right) => self.pipe(left, right, ast),
+      ast::Value::Binary(left, op, right) => self.binary(left, *op, right, ast),
+      ast::Value::Call(callee, args) => self.call_expression(callee, args, ast),
+      ast::Value::List(items) => self.list(items, ast),
+      ast::Value::Conditional(condition, then, otherwise) => self.branch(condition, then, Some(otherwise)),
+      ast::Value::If(condition, then, otherwise) => self.branch(condition, then, otherwise.as_deref()),
+      ast::Value::Lambda(params, body) => self.function(None, params, body),
+      ast::Value::FunctionDefinition(name, params, body) => self.function_definition(name, params, body),
+      ast::Value::Expression(e) => self.compute(e),
+      ast::Value::Program(lines) => self.lines(lines),
+      ast::Value::Block(lines) => self.block(lines),
+      ast::Value::While(condition, body) => self.while_loop(condition, body, ast),
+      ast::Value::For(id, iterable, body) => self.for_loop(id, iterable, body, ast),
+      ast::Value::Return(Some(expr)) => self.return_value(expr, ast),
+      ast::Value::Declaration(id, expr) => self.store(id, expr, Scope::define),
+      ast::Value::Assignment(id, expr) => self.store(id, expr, Scope::assign),
+      ast::Value::CompoundAssignment(id, op, expr) => self.compound_assignment(id, *op, expr, ast),
+      ast::Value::PrefixIncrement(op, id) => self.increment(id, *op, ast).map(|(_, new)| new),
+      ast::Value::PostfixIncrement(id, op) => self.increment(id, *op, ast).map(|(old, _)| old),
+    }
   }
 }
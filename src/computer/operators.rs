@@ -0,0 +1,144 @@
+use std::cmp::Ordering;
+
+use crate::ast::{BinaryOp, UnaryOp};
+
+use super::{error::ErrorKind, value::Value};
+
+fn unsupported(op: BinaryOp, left: &Value, right: &Value) -> ErrorKind {
+  ErrorKind::UnsupportedOperands(op.to_string(), left.type_name(), right.type_name())
+}
+
+fn integer(op: BinaryOp, left: i128, right: i128) -> Result<Value, ErrorKind> {
+  let result = match op {
+    BinaryOp::Add => left.checked_add(right),
+    BinaryOp::Sub => left.checked_sub(right),
+    BinaryOp::Mul => left.checked_mul(right),
+    BinaryOp::Div | BinaryOp::Rem if right == 0 => return Err(ErrorKind::DivisionByZero),
+    BinaryOp::Div => left.checked_div(right),
+    BinaryOp::Rem => left.checked_rem(right),
+    // negative powers are fractions, so they are computed in floats
+    BinaryOp::Pow if right < 0 => return float(op, left as f64, right as f64),
+    BinaryOp::Pow => u32::try_from(right).ok().and_then(|right| left.checked_pow(right)),
+    _ => return Err(unsupported(op, &Value::Int(left), &Value::Int(right))),
+  };
+
+  result.map(Value::Int).ok_or(ErrorKind::IntegerOverflow)
+}
+
+fn bitwise(op: BinaryOp, left: i128, right: i128) -> Result<Value, ErrorKind> {
+  let shift = || match u32::try_from(right) {
+    Ok(shift) if shift < i128::BITS => Ok(shift),
+    _ => Err(ErrorKind::ShiftOutOfRange(right)),
+  };
+
+  let result = match op {
+    BinaryOp::BitAnd => left & right,
+    BinaryOp::BitOr => left | right,
+    BinaryOp::BitXor => left ^ right,
+    BinaryOp::Shl => left << shift()?,
+    BinaryOp::Shr => left >> shift()?,
+    _ => return Err(unsupported(op, &Value::Int(left), &Value::Int(right))),
+  };
+
+  Ok(Value::Int(result))
+}
+
+fn float(op: BinaryOp, left: f64, right: f64) -> Result<Value, ErrorKind> {
+  let result = match op {
+    BinaryOp::Add => left + right,
+    BinaryOp::Sub => left - right,
+    BinaryOp::Mul => left * right,
+    BinaryOp::Div => left / right,
+    BinaryOp::Rem => left % right,
+    BinaryOp::Pow => left.powf(right),
+    _ => return Err(unsupported(op, &Value::Float(left), &Value::Float(right))),
+  };
+
+  Ok(Value::Float(result))
+}
+
+fn equals(left: &Value, right: &Value) -> bool {
+  match (left, right) {
+    (Value::Int(left), Value::Float(right)) => *left as f64 == *right,
+    (Value::Float(left), Value::Int(right)) => *left == *right as f64,
+    // items are compared like values, so that `[1] == [1.0]` just like `1 == 1.0`
+    (Value::List(left, _), Value::List(right, _)) => {
+      left.len() == right.len() && left.iter().zip(right.iter()).all(|(left, right)| equals(left, right))
+    }
+    (left, right) => left == right,
+  }
+}
+
+fn compare(op: BinaryOp, left: &Value, right: &Value) -> Result<Option<Ordering>, ErrorKind> {
+  match (left, right) {
+    (Value::Int(left), Value::Int(right)) => Ok(left.partial_cmp(right)),
+    (Value::Int(left), Value::Float(right)) => Ok((*left as f64).partial_cmp(right)),
+    (Value::Float(left), Value::Int(right)) => Ok(left.partial_cmp(&(*right as f64))),
+    (Value::Float(left), Value::Float(right)) => Ok(left.partial_cmp(right)),
+    (Value::Char(left), Value::Char(right)) => Ok(left.partial_cmp(right)),
+    (Value::String(left), Value::String(right)) => Ok(left.partial_cmp(right)),
+    (left, right) => Err(unsupported(op, left, right)),
+  }
+}
+
+pub fn unary(op: UnaryOp, value: Value) -> Result<Value, ErrorKind> {
+  match (op, value) {
+    (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+    (UnaryOp::BitNot, Value::Int(i)) => Ok(Value::Int(!i)),
+    (UnaryOp::Plus, value @ (Value::Int(_) | Value::Float(_))) => Ok(value),
+    (UnaryOp::Minus, Value::Int(i)) => i.checked_neg().map(Value::Int).ok_or(ErrorKind::IntegerOverflow),
+    (UnaryOp::Minus, Value::Float(f)) => Ok(Value::Float(-f)),
+    (op, value) => Err(ErrorKind::UnsupportedOperand(op.to_string(), value.type_name())),
+  }
+}
+
+fn arithmetic(left: Value, op: BinaryOp, right: Value) -> Result<Value, ErrorKind> {
+  match (left, right) {
+    (Value::Int(left), Value::Int(right)) => integer(op, left, right),
+    (Value::Int(left), Value::Float(right)) => float(op, left as f64, right),
+    (Value::Float(left), Value::Int(right)) => float(op, left, right as f64),
+    (Value::Float(left), Value::Float(right)) => float(op, left, right),
+    (Value::String(left), Value::String(right)) if op == BinaryOp::Add => Ok(Value::String(left + &right)),
+    (Value::String(mut left), Value::Char(right)) if op == BinaryOp::Add => {
+      left.push(right);
+      Ok(Value::String(left))
+    }
+    (Value::Char(left), Value::String(right)) if op == BinaryOp::Add => Ok(Value::String(format!("{}{}", left, right))),
+    (left, right) => Err(unsupported(op, &left, &right)),
+  }
+}
+
+pub fn binary(left: Value, op: BinaryOp, right: Value) -> Result<Value, ErrorKind> {
+  match op {
+    BinaryOp::Eq => Ok(Value::Bool(equals(&left, &right))),
+    BinaryOp::Ne => Ok(Value::Bool(!equals(&left, &right))),
+    BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Le | BinaryOp::Ge => {
+      let result = compare(op, &left, &right)?.is_some_and(|ordering| match op {
+        BinaryOp::Lt => ordering.is_lt(),
+        BinaryOp::Gt => ordering.is_gt(),
+        BinaryOp::Le => ordering.is_le(),
+        _ => ordering.is_ge(),
+      });
+
+      Ok(Value::Bool(result))
+    }
+    BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor | BinaryOp::Shl | BinaryOp::Shr => match (left, right) {
+      (Value::Int(left), Value::Int(right)) => bitwise(op, left, right),
+      (left, right) => Err(unsupported(op, &left, &right)),
+    },
+    BinaryOp::Range => match (left, right) {
+      (Value::Int(start), Value::Int(end)) => Ok(Value::Range(start, end)),
+      (left, right) => Err(unsupported(op, &left, &right)),
+    },
+    BinaryOp::And | BinaryOp::Or => match (left, right) {
+      (Value::Bool(left), Value::Bool(right)) if op == BinaryOp::And => Ok(Value::Bool(left && right)),
+      (Value::Bool(left), Value::Bool(right)) => Ok(Value::Bool(left || right)),
+      (left, right) => Err(unsupported(op, &left, &right)),
+    },
+    BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem | BinaryOp::Pow => {
+      arithmetic(left, op, right)
+    }
+    // calling the function needs the computer, which pipes values before they get here
+    BinaryOp::Pipe => Err(unsupported(op, &left, &right)),
+  }
+}
@@ -0,0 +1,121 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use super::value::{Closure, Function, Value};
+
+pub type ScopeRef = Rc<RefCell<Scope>>;
+
+/// Variables of a single scope, chained to the enclosing one
+pub struct Scope {
+  variables: HashMap<String, Value>,
+  parent: Option<ScopeRef>,
+}
+
+impl Scope {
+  pub fn new(parent: Option<ScopeRef>) -> ScopeRef {
+    Rc::new(RefCell::new(Scope {
+      variables: HashMap::new(),
+      parent,
+    }))
+  }
+
+  pub fn get(&self, name: &str) -> Option<Value> {
+    match self.variables.get(name) {
+      Some(value) => Some(Self::load(value)),
+      None => self.parent.as_ref()?.borrow().get(name),
+    }
+  }
+
+  /// Defines the variable in this scope, shadowing variables of the enclosing ones
+  pub fn define(&mut self, name: String, value: Value) {
+    let value = self.store(value);
+    self.variables.insert(name, value);
+  }
+
+  /// Updates the variable in the closest scope it is defined in, or defines it in this one
+  pub fn assign(&mut self, name: String, value: Value) {
+    if !self.variables.contains_key(&name) {
+      if let Some(parent) = &self.parent {
+        if parent.borrow().get(&name).is_some() {
+          return parent.borrow_mut().assign(name, value);
+        }
+      }
+    }
+
+    self.define(name, value);
+  }
+
+  pub fn variables(&self) -> Vec<(String, Value)> {
+    let mut variables: Vec<_> = self.variables.iter().map(|(k, v)| (k.clone(), Self::load(v))).collect();
+    variables.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    variables
+  }
+
+  /// Removes every variable, freeing whatever only they kept alive
+  pub fn clear(&mut self) {
+    free(self.variables.drain().map(|(_, value)| value).collect(), vec![]);
+  }
+
+  /// Makes functions defined in this scope hold it weakly, see `Closure`
+  fn store(&self, value: Value) -> Value {
+    match value {
+      Value::Function(function) => match &function.closure {
+        Closure::Strong(scope) if std::ptr::eq(scope.as_ptr(), self) => Value::Function(Rc::new(Function {
+          closure: Closure::Weak(Rc::downgrade(scope)),
+          ..(*function).clone()
+        })),
+        _ => Value::Function(function),
+      },
+      value => value,
+    }
+  }
+
+  /// Hands out functions stored in this scope holding it strongly again, so they can outlive it
+  fn load(value: &Value) -> Value {
+    match value {
+      Value::Function(function) if matches!(function.closure, Closure::Weak(_)) => Value::Function(Rc::new(Function {
+        closure: Closure::Strong(function.scope()),
+        ..(**function).clone()
+      })),
+      value => value.clone(),
+    }
+  }
+}
+
+impl Drop for Scope {
+  fn drop(&mut self) {
+    let values = self.variables.drain().map(|(_, value)| value).collect();
+    free(values, self.parent.take().into_iter().collect());
+  }
+}
+
+/// Dropping a value can drop the last reference to a scope, like the one captured by a function, which drops the
+/// values stored in it, and so on for as long as the code chained them. They are taken apart in a loop instead of
+/// recursively, so that long chains cannot overflow the stack.
+fn free(mut values: Vec<Value>, mut scopes: Vec<ScopeRef>) {
+  loop {
+    if let Some(scope) = scopes.pop() {
+      if let Ok(scope) = Rc::try_unwrap(scope) {
+        let mut scope = scope.into_inner();
+        values.extend(scope.variables.drain().map(|(_, value)| value));
+        scopes.extend(scope.parent.take());
+      }
+    } else if let Some(value) = values.pop() {
+      match value {
+        Value::List(items, _) => values.extend(Rc::try_unwrap(items).unwrap_or_default()),
+        Value::Function(function) => {
+          if let Ok(Function {
+            closure: Closure::Strong(scope),
+            ..
+          }) = Rc::try_unwrap(function)
+          {
+            scopes.push(scope);
+          }
+        }
+        _ => {}
+      }
+    } else {
+      break;
+    }
+  }
+}
@@ -0,0 +1,476 @@
+use std::rc::Rc;
+
+use super::{Computer, ErrorKind, Value};
+use crate::{
+  ast::{self, ErrorCode},
+  source_code::{SourceCode, SourceMap},
+  stack::{self, MAX_NESTING},
+  tokeniser,
+};
+
+macro_rules! test {
+  ($name: ident, $code: expr => error) => {
+    #[test]
+    fn $name() {
+      let mut computer = Computer::new();
+      let result = computer.compute(&ast::from_string($code));
+
+      assert!(result.is_err(), "expected an error, got {:?}", result);
+    }
+  };
+
+  ($name: ident, $code: expr => $result: expr) => {
+    #[test]
+    fn $name() {
+      let mut computer = Computer::new();
+      let result = computer.compute(&ast::from_string($code)).map(|value| value.to_string());
+
+      assert_eq!(result, Ok($result.to_string()));
+    }
+  };
+}
+
+test!(int_literal, "42" => "42");
+test!(float_literal, "4.5" => "4.5");
+test!(char_literal, "'c'" => "'c'");
+test!(string_literal, r#""test""# => r#""test""#);
+test!(empty_program, ";" => "null");
+
+test!(int_arithmetic, "2 * (3 + 4) - 5" => "9");
+test!(int_division, "7 / 2" => "3");
+test!(int_remainder, "7 % 3" => "1");
+test!(int_division_by_zero, "1 / 0" => error);
+test!(float_arithmetic, "7.0 / 2" => "3.5");
+test!(mixed_arithmetic, "1 + .5" => "1.5");
+test!(int_large_arithmetic, "9223372036854775808 * 9223372036854775808 / 3" => "28356863910078205288614550619314017621");
+test!(int_max, "170141183460469231731687303715884105727" => "170141183460469231731687303715884105727");
+test!(int_min, "-170141183460469231731687303715884105728" => "-170141183460469231731687303715884105728");
+test!(int_literal_too_big, "170141183460469231731687303715884105728" => error);
+test!(int_addition_overflow, "170141183460469231731687303715884105727 + 1" => error);
+test!(int_multiplication_overflow, "18446744073709551616 * 18446744073709551616" => error);
+test!(int_negation_overflow, "a = -170141183460469231731687303715884105728; -a" => error);
+test!(int_division_overflow, "-170141183460469231731687303715884105728 / -1" => error);
+test!(int_stays_exact, "9007199254740993 + 0" => "9007199254740993");
+test!(int_promoted_to_float, "9007199254740993 + 0.0" => "9007199254740992.0");
+test!(unary_minus, "-(2 - 5)" => "3");
+test!(power_int, "2 ** 100" => "1267650600228229401496703205376");
+test!(power_right_associative, "2 ** 3 ** 2" => "512");
+test!(power_before_negation, "-2 ** 2" => "-4");
+test!(power_negative_exponent, "2 ** -1" => "0.5");
+test!(power_float, "4 ** 0.5" => "2.0");
+test!(power_overflow, "2 ** 127" => error);
+
+test!(comparison_int, "1 < 2" => "true");
+test!(comparison_mixed, "2 >= 2.5" => "false");
+test!(comparison_nan, "a = 0.0 / 0.0; a < 1 || a >= 1" => "false");
+test!(comparison_char, "'a' <= 'b'" => "true");
+test!(comparison_string, r#""abc" > "abd""# => "false");
+test!(comparison_precedence, "1 + 1 < 1 * 3" => "true");
+test!(comparison_of_different_types, r#"1 < "a""# => error);
+test!(equality_int, "2 * 2 == 4" => "true");
+test!(equality_mixed, "1 == 1.0" => "true");
+test!(equality_of_different_types, r#"1 != "1""# => "true");
+test!(equality_of_bools, "(1 < 2) == (2 < 3)" => "true");
+test!(logical_not, "!(1 > 2)" => "true");
+test!(logical_not_of_int, "!1" => error);
+test!(logical_and, "1 < 2 && 2 < 1" => "false");
+test!(logical_or, "1 < 2 || 2 < 1" => "true");
+test!(logical_precedence, "1 > 2 && 1 > 2 || 1 < 2" => "true");
+test!(logical_short_circuit_and, "1 > 2 && unknown" => "false");
+test!(logical_short_circuit_or, "1 < 2 || unknown" => "true");
+test!(logical_evaluates_right, "1 < 2 && unknown" => error);
+test!(logical_of_int, "1 && 1 < 2" => error);
+
+test!(bitwise_and, "0x7f & 0x18" => "24");
+test!(bitwise_or, "0x10 | 3" => "19");
+test!(bitwise_xor, "0xff ^ 0xf" => "240");
+test!(bitwise_not, "~0" => "-1");
+test!(bitwise_precedence, "1 | 6 ^ 3 & 5" => "7");
+test!(bitwise_below_equality, "5 & 4 == 4" => error);
+test!(bitwise_of_floats, "1.0 & 1" => error);
+test!(bitwise_not_of_float, "~1.5" => error);
+test!(shift_left, "1 << 3 + 1" => "16");
+test!(shift_right, "-16 >> 2" => "-4");
+test!(shift_to_sign_bit, "1 << 127" => "-170141183460469231731687303715884105728");
+test!(shift_out_of_range, "1 << 128" => error);
+test!(shift_by_negative, "1 >> -1" => error);
+test!(shift_of_float, "1.0 << 1" => error);
+
+test!(string_concatenation, r#""ab" + "cd""# => r#""abcd""#);
+test!(string_char_concatenation, r#""ab" + 'c'"# => r#""abc""#);
+test!(char_string_concatenation, r#"'a' + "bc""# => r#""abc""#);
+test!(string_times_char, r#""a" * 'b'"# => error);
+test!(string_minus_string, r#""a" - "b""# => error);
+test!(negated_string, r#"-"a""# => error);
+
+test!(compound_addition, "a = 1; a += 2 * 3; a" => "7");
+test!(compound_subtraction, "a = 1; a -= 3" => "-2");
+test!(compound_multiplication, "a = 1.5; a *= 2" => "3.0");
+test!(compound_division, "a = 7; a /= 2; a" => "3");
+test!(compound_remainder, "a = 7; a %= 4" => "3");
+test!(compound_concatenation, r#"a = "ab"; a += 'c'"# => r#""abc""#);
+test!(compound_of_unknown_variable, "a += 1" => error);
+test!(compound_type_error, r#"a = 1; a *= "b""# => error);
+test!(prefix_increment, "a = 1; ++a + a" => "4");
+test!(prefix_decrement, "a = 1; --a; a" => "0");
+test!(postfix_increment, "a = 1; a++ + a" => "3");
+test!(postfix_decrement, "a = 1.5; a--; a" => "0.5");
+test!(increment_of_string, r#"a = "a"; a++"# => error);
+test!(increment_overflow, "a = 170141183460469231731687303715884105727; ++a" => error);
+test!(builtin_sqrt, "sqrt(16)" => "4.0");
+test!(builtin_abs_int, "abs(-3)" => "3");
+test!(builtin_abs_float, "abs(-2.5)" => "2.5");
+test!(builtin_abs_overflow, "abs(-170141183460469231731687303715884105728)" => error);
+test!(builtin_pow_int, "pow(2, 100)" => "1267650600228229401496703205376");
+test!(builtin_pow_negative_exponent, "pow(2, -1)" => "0.5");
+test!(builtin_pow_float, "pow(2.0, 3)" => "8.0");
+test!(builtin_pow_overflow, "pow(2, 127)" => error);
+test!(builtin_exp_ln, "ln(exp(2))" => "2.0");
+test!(builtin_log10, "log10(1000)" => "3.0");
+test!(builtin_trigonometry, "sin(0) + cos(0) + tan(0)" => "1.0");
+test!(builtin_inverse_trigonometry, "asin(0) + acos(1) + atan(0)" => "0.0");
+test!(builtin_floor, "floor(2.7)" => "2.0");
+test!(builtin_ceil, "ceil(2.2)" => "3.0");
+test!(builtin_round, "round(2.5) + round(7)" => "10.0");
+test!(builtin_round_int, "round(7)" => "7");
+test!(builtin_min, "min(3, 1, 2)" => "1");
+test!(builtin_max_mixed, "max(1, 2.5, 2)" => "2.5");
+test!(builtin_max_mixed_int, "max(3, 2.5)" => "3.0");
+test!(builtin_min_nan_last, "min(1, 0.0 / 0.0)" => "NaN");
+test!(builtin_min_nan_first, "min(0.0 / 0.0, 1)" => "NaN");
+test!(builtin_max_nan_last, "max(1, 0.0 / 0.0, 2)" => "NaN");
+test!(builtin_max_nan_first, "max(0.0 / 0.0, 1)" => "NaN");
+test!(builtin_in_expression, "a = 3; b = 4; sqrt(pow(a, 2) + pow(b, 2)) * 2" => "10.0");
+test!(builtin_unknown, "foo(1)" => error);
+test!(builtin_wrong_arity, "pow(2)" => error);
+test!(builtin_no_arguments, "max()" => error);
+test!(builtin_wrong_type, r#"sqrt("4")"# => error);
+test!(call_of_literal, "1(2)" => error);
+test!(pipe_to_builtin, "-3 |> abs" => "3");
+test!(pipe_chain, "fn double(x) = x * 2; 3 |> double |> double" => "12");
+test!(pipe_to_call_result, "fn adder(x) = (y) => x + y; 1 |> adder(2)" => "3");
+test!(pipe_to_value, "1 |> 2" => error);
+test!(assignment_keeps_type, "a = 'x'; a" => "'x'");
+test!(unknown_variable, "a + 1" => error);
+
+#[test]
+fn error_keeps_kind_and_span() {
+  let mut computer = Computer::new();
+  let error = computer.compute(&ast::from_string("a = 1\nb = a + (a * c)")).unwrap_err();
+
+  assert_eq!(*error.kind, ErrorKind::VariableNotFound("c".to_string()));
+  assert_eq!((error.start.line, error.start.column), (1, 13));
+  assert_eq!((error.end.line, error.end.column), (1, 14));
+}
+
+#[test]
+fn syntax_error_keeps_span() {
+  let mut computer = Computer::new();
+  let error = computer.compute(&ast::from_string("1 + 'ab'")).unwrap_err();
+
+  assert_eq!(
+    *error.kind,
+    ErrorKind::Syntax(ErrorCode::InvalidToken(tokeniser::ErrorCode::CharLiteralTooLong))
+  );
+  assert_eq!((error.start.column, error.end.column), (4, 8));
+}
+
+#[test]
+fn undefined_variable_in_update_has_span() {
+  let mut computer = Computer::new();
+  let error = computer.compute(&ast::from_string("a = 1; b++")).unwrap_err();
+
+  assert_eq!(*error.kind, ErrorKind::VariableNotFound("b".to_string()));
+  assert_eq!((error.start.column, error.end.column), (7, 8));
+}
+
+#[test]
+fn call_errors_have_span_of_call() {
+  let mut computer = Computer::new();
+  let error = computer.compute(&ast::from_string("1 + pow(2, 'a')")).unwrap_err();
+
+  assert_eq!(*error.kind, ErrorKind::InvalidArgument("pow".to_string(), "char"));
+  assert_eq!((error.start.column, error.end.column), (4, 15));
+
+  let error = computer.compute(&ast::from_string("1 + min()")).unwrap_err();
+
+  assert_eq!(error.to_string(), "1:5: `min` expects at least 1 argument, got 0");
+  assert_eq!((error.start.column, error.end.column), (4, 9));
+}
+
+#[test]
+fn compound_terms_have_their_own_span() {
+  let cases = [
+    ("[1, 2, 3] * \"a\"", (0, 0), (0, 15)),
+    ("(1 + 2) * \"a\"", (0, 0), (0, 13)),
+    ("((x) => x) + 1", (0, 0), (0, 14)),
+    ("if true { 1 } else { 2 } + \"a\"", (0, 0), (0, 30)),
+    ("a = 0\nwhile true {\n  a = a + 1\n}", (1, 0), (3, 1)),
+  ];
+
+  for (code, start, end) in cases {
+    let mut computer = Computer::new();
+    computer.set_iteration_limit(10);
+    let error = computer.compute(&ast::from_string(code)).unwrap_err();
+
+    assert_eq!((error.start.line, error.start.column), start, "{}", code);
+    assert_eq!((error.end.line, error.end.column), end, "{}", code);
+  }
+}
+
+#[test]
+fn errors_point_into_the_file_where_they_happen() {
+  let mut computer = Computer::new();
+  let mut source_map = SourceMap::new();
+  let library = source_map.add(SourceCode::with_name(
+    "library.nl".to_string(),
+    "fn f(x) = x + y".to_string(),
+  ));
+  let script = source_map.add(SourceCode::with_name("script.nl".to_string(), "f(1)".to_string()));
+
+  computer.compute(&ast::from_source_code(&source_map[library])).unwrap();
+  let error = computer.compute(&ast::from_source_code(&source_map[script])).unwrap_err();
+
+  assert_eq!(error.start.file, library);
+  assert_eq!(source_map.locate(error.start).to_string(), "library.nl:1:15");
+  assert_eq!(error.describe(&source_map), "library.nl:1:15: variable `y` not found");
+}
+
+test!(function_definition, "fn area(w, h) = w * h" => "<fn area(w, h)>");
+test!(function_call, "fn area(w, h) = w * h; area(3, 4)" => "12");
+test!(function_recursive, "fn down(n) = n <= 0 || down(n - 1); down(5)" => "true");
+test!(function_uses_globals, "k = 3; fn scale(x) = x * k; scale(2)" => "6");
+test!(function_params_are_local, "x = 1; fn f(x) = x + 1; f(10) + x" => "12");
+test!(function_assignment_updates_globals, "n = 0; fn bump() = n++; bump(); bump(); n" => "2");
+test!(function_local_variable_does_not_leak, "fn f() = tmp = 1; f(); tmp" => error);
+test!(function_wrong_arity, "fn f(a, b) = a; f(1)" => error);
+test!(function_shadows_builtin, "fn sqrt(x) = x; sqrt(4)" => "4");
+test!(lambda, "(x, y) => x + y" => "<fn(x, y)>");
+test!(lambda_call, "add = (x, y) => x + y; add(1, 2)" => "3");
+test!(lambda_called_directly, "((x) => x * 2)(21)" => "42");
+test!(lambda_no_parameters, "f = () => 7; f()" => "7");
+test!(function_equality, "f = () => 1; g = f; f == g" => "true");
+test!(function_inequality, "f = () => 1; g = () => 1; f == g" => "false");
+test!(call_of_variable, "a = 1; a(2)" => error);
+test!(closure_captures_parameter, "fn adder(x) = (y) => x + y; add2 = adder(2); add2(3)" => "5");
+test!(closure_called_directly, "fn adder(x) = (y) => x + y; adder(1)(2)" => "3");
+test!(closure_sees_later_updates, "k = 1; scale = (x) => x * k; k = 10; scale(2)" => "20");
+test!(closure_keeps_own_environment, "fn const(x) = () => x; a = const(1); b = const(2); a() + b()" => "3");
+test!(nested_closures, "fn add3(a) = (b) => (c) => a + b + c; add3(1)(2)(3)" => "6");
+test!(inner_function_outlives_its_scope, "fn make() = { fn fact(n) = if n <= 1 { 1 } else { n * fact(n - 1) }; fact }; make()(5)" => "120");
+test!(function_as_argument, "fn twice(f, x) = f(f(x)); twice((x) => x * 3, 2)" => "18");
+test!(builtin_as_value, "f = sqrt; f(16)" => "4.0");
+test!(builtin_display, "abs" => "<builtin abs>");
+test!(builtin_passed_to_function, "fn apply(f, x) = f(x); apply(abs, -2)" => "2");
+test!(list_literal, "[1, 'a', \"b\", [2.5]]" => "[1, 'a', \"b\", [2.5]]");
+test!(list_empty, "[]" => "[]");
+test!(list_equality, "[1, 2] == [1, 2]" => "true");
+test!(list_equality_mixed, "[1, [2.0]] == [1.0, [2]]" => "true");
+test!(list_inequality_mixed, "[1, [2.5]] != [1.0, [2]]" => "true");
+test!(list_equality_of_different_lengths, "[1] == [1, 1]" => "false");
+test!(map, "map((x) => x * x, [1, 2, 3])" => "[1, 4, 9]");
+test!(map_with_builtin, "map(abs, [-1, 2, -3])" => "[1, 2, 3]");
+test!(map_with_closure, "k = 10; map((x) => x + k, [1, 2])" => "[11, 12]");
+test!(map_of_non_list, "map(abs, 1)" => error);
+test!(map_with_non_function, "map(1, [1])" => error);
+test!(map_with_wrong_arity, "map((a, b) => a, [1])" => error);
+test!(filter, "filter((x) => x % 2 == 0, [1, 2, 3, 4])" => "[2, 4]");
+test!(filter_with_non_bool_result, "filter((x) => x, [1])" => error);
+test!(conditional_true, "1 < 2 ? 'a' : 'b'" => "'a'");
+test!(conditional_false, "1 > 2 ? 'a' : 'b'" => "'b'");
+test!(conditional_right_associative, "x = 0; x < 0 ? -1 : x == 0 ? 0 : 1" => "0");
+test!(conditional_is_lazy, "true_ = 1 == 1; true_ ? 1 : 1 / 0" => "1");
+test!(conditional_needs_bool, "1 ? 2 : 3" => error);
+test!(if_true, "if 1 < 2 { 'a' } else { 'b' }" => "'a'");
+test!(if_false, "if 1 > 2 { 'a' } else { 'b' }" => "'b'");
+test!(if_without_else, "if 1 > 2 { 'a' }" => "null");
+test!(if_else_if, "x = 5; if x < 0 { -1 } else if x == 0 { 0 } else { 1 }" => "1");
+test!(if_is_lazy, "if 1 == 1 { 1 } else { undefined }" => "1");
+test!(if_block_returns_last_line, "if 1 == 1 { a = 2; a * 3 }" => "6");
+test!(if_empty_block, "if 1 == 1 {}" => "null");
+test!(if_needs_bool, "if 1 { 2 }" => error);
+test!(if_in_function, "fn tariff(kwh) = if kwh <= 100 { kwh * 5 } else { 500 + (kwh - 100) * 7 }; tariff(150)" => "850");
+test!(recursion_with_conditional, "fn fact(n) = n <= 1 ? 1 : n * fact(n - 1); fact(10)" => "3628800");
+test!(range, "1..2 + 3" => "1..5");
+test!(range_binds_tighter_than_comparison, "r = 1..3; r == 1..3 && r != 0..3" => "true");
+test!(range_of_floats, "1.5..2" => error);
+test!(block_value, "{ a = 2; a * 3 }" => "6");
+test!(block_variables_are_local, "{ a = 2 }; a" => error);
+test!(block_updates_outer_variables, "a = 1; { a = 2 }; a" => "2");
+test!(while_loop, "i = 0; s = 0; while i < 5 { i += 1; s += i }; s" => "15");
+test!(while_loop_value, "i = 0; while i < 5 { i += 1 }" => "null");
+test!(while_needs_bool, "while 1 { }" => error);
+test!(for_loop, "s = 0; for i in 0..5 { s += i }; s" => "10");
+test!(for_loop_empty_range, "s = 0; for i in 5..0 { s += 1 }; s" => "0");
+test!(for_loop_over_list, "s = \"\"; for c in ['a', 'b'] { s = s + c }; s" => "\"ab\"");
+test!(for_loop_variable_is_local, "for i in 0..3 {}; i" => error);
+test!(for_over_int, "for i in 5 {}" => error);
+test!(break_loop, "i = 0; while i < 100 { if i == 7 { break }; i++ }; i" => "7");
+test!(continue_loop, "s = 0; for i in 0..10 { if i % 2 == 1 { continue }; s += i }; s" => "20");
+test!(break_inner_loop, "n = 0; for i in 0..3 { for j in 0..10 { if j == 2 { break }; n++ } }; n" => "6");
+test!(loop_in_function, "fn sum(n) = { s = 0; for i in 1..n + 1 { s += i }; s }; sum(100)" => "5050");
+test!(closure_captures_iteration, "f = 0; for i in 0..3 { if i == 1 { f = () => i } }; f()" => "1");
+test!(
+  amortisation_schedule,
+  "balance = 1000.0; rate = 0.01; payment = 100.0; months = 0
+   while balance > 0 { balance = balance * (1 + rate) - payment; months++ }
+   months" => "11"
+);
+test!(bool_literals, "true && !false" => "true");
+test!(null_literal, "null" => "null");
+test!(null_equality, "if false { 1 } == null" => "true");
+test!(declaration, "let a = 2; a * 3" => "6");
+test!(declaration_shadows_in_block, "a = 1; { let a = 2; a = 3 }; a" => "1");
+test!(declaration_redefines, "let a = 1; let a = a + 1; a" => "2");
+test!(declaration_in_function_is_local, "x = 1; fn f() = { let x = 5; x }; f() + x" => "6");
+test!(return_value, "fn sign(x) = { if x < 0 { return -1 }; 1 }; sign(-5) + 10 * sign(5)" => "9");
+test!(return_without_value, "fn f() = { return; 1 }; f()" => "null");
+test!(return_from_loop, "fn find(n) = { for i in 0..100 { if i * i >= n { return i } }; -1 }; find(50)" => "8");
+test!(return_from_inner_function, "fn outer() = { f = () => { return 1 }; f() + 1 }; outer()" => "2");
+test!(unbounded_recursion_in_blocks, "fn f(n) = { if true { f(n + 1) } else { null } }; f(0)" => error);
+test!(unbounded_recursion, "fn f(n) = f(n + 1); f(0)" => error);
+
+#[test]
+fn recursion_limit_is_configurable() {
+  let mut computer = Computer::new();
+  computer.set_recursion_limit(10);
+
+  let code = "fn f(n) = n <= 0 || f(n - 1)";
+  computer.compute(&ast::from_string(code)).unwrap();
+
+  assert_eq!(computer.compute(&ast::from_string("f(9)")), Ok(Value::Bool(true)));
+
+  let error = computer.compute(&ast::from_string("f(10)")).unwrap_err();
+  assert_eq!(*error.kind, ErrorKind::RecursionLimitExceeded(10));
+
+  // the computer recovers after the failed call
+  assert_eq!(computer.compute(&ast::from_string("f(3)")), Ok(Value::Bool(true)));
+}
+
+#[test]
+fn recursion_reaches_the_default_limit() {
+  stack::run(|| {
+    let mut computer = Computer::new();
+    let code = "fn sum(n) = if n == 0 { 0 } else { n + sum(n - 1) }; fn total(n) = { if n == 0 { 0 } else { n + total(n - 1) } }";
+    computer.compute(&ast::from_string(code)).unwrap();
+
+    for code in ["sum(99)", "total(99)"] {
+      assert_eq!(
+        computer.compute(&ast::from_string(code)),
+        Ok(Value::Int(4950)),
+        "{}",
+        code
+      );
+    }
+
+    let error = computer.compute(&ast::from_string("sum(100)")).unwrap_err();
+    assert_eq!(*error.kind, ErrorKind::RecursionLimitExceeded(100));
+
+    // deeper recursion fits as well, as long as the limit allows it
+    computer.set_recursion_limit(1000);
+    assert_eq!(
+      computer.compute(&ast::from_string("total(999)")),
+      Ok(Value::Int(499500))
+    );
+  });
+}
+
+#[test]
+fn long_chains_of_operators_are_computed() {
+  stack::run(|| {
+    let mut computer = Computer::new();
+    let code = format!("total = {}; total", vec!["1"; 1000].join(" + "));
+
+    assert_eq!(computer.compute(&ast::from_string(&code)), Ok(Value::Int(1000)));
+  });
+}
+
+#[test]
+fn nesting_fails_before_the_stack_overflows() {
+  stack::run(|| {
+    let blocks = format!("fn f(n) = {}f(n + 1){}; f(0)", "{ ".repeat(100), " }".repeat(100));
+    let brackets = format!("fn f(n) = {}f(n + 1){}; f(0)", "(".repeat(20), ")".repeat(20));
+    let mut computer = Computer::new();
+    // the nesting of computations is limited even when calls are not
+    computer.set_recursion_limit(usize::MAX);
+
+    for code in [blocks, brackets, "fn f(n) = n <= 0 || f(n - 1); f(100000)".to_string()] {
+      let error = computer.compute(&ast::from_string(&code)).unwrap_err();
+      assert_eq!(*error.kind, ErrorKind::NestedTooDeeply(MAX_NESTING), "{}", code);
+    }
+
+    // the computer recovers after the failed computation
+    assert_eq!(computer.compute(&ast::from_string("f(3)")), Ok(Value::Bool(true)));
+  });
+}
+
+#[test]
+fn functions_do_not_keep_their_scope_alive() {
+  let mut computer = Computer::new();
+  let code = "fn make() = { fn inner() = 1; inner }; f = make(); f()";
+  assert_eq!(computer.compute(&ast::from_string(code)), Ok(Value::Int(1)));
+
+  let Some(Value::Function(f)) = computer.globals.borrow().get("f") else {
+    panic!("`f` is not a function")
+  };
+  let block = Rc::downgrade(&f.scope());
+  drop(f);
+  assert!(block.upgrade().is_some());
+
+  computer.compute(&ast::from_string("f = 0")).unwrap();
+  assert!(block.upgrade().is_none());
+
+  let globals = Rc::downgrade(&computer.globals);
+  drop(computer);
+  assert!(globals.upgrade().is_none());
+}
+
+#[test]
+fn long_chains_of_closures_are_dropped() {
+  let mut computer = Computer::new();
+  let code = "fn wrap(x) = () => x; a = 1; for i in 0..100000 { a = wrap(a) }";
+  computer.compute(&ast::from_string(code)).unwrap();
+
+  let globals = Rc::downgrade(&computer.globals);
+  drop(computer);
+  assert!(globals.upgrade().is_none());
+}
+
+#[test]
+fn lists_cannot_nest_deeper_than_the_limit() {
+  let mut computer = Computer::new();
+
+  for wrap in ["[a]", "[1, [a], 2]", "map((x) => [x], [a])"] {
+    let code = format!("a = []; for i in 0..1000 {{ a = {} }}", wrap);
+    let error = computer.compute(&ast::from_string(&code)).unwrap_err();
+    assert_eq!(*error.kind, ErrorKind::ListNestedTooDeeply(256), "{}", code);
+  }
+
+  let code = "a = []; for i in 0..254 { a = [a] }; [a] == [a]";
+  assert_eq!(computer.compute(&ast::from_string(code)), Ok(Value::Bool(true)));
+}
+
+#[test]
+fn iteration_limit_is_configurable() {
+  let mut computer = Computer::new();
+  computer.set_iteration_limit(100);
+
+  let error = computer.compute(&ast::from_string("i = 0; while 1 == 1 { i++ }")).unwrap_err();
+  assert_eq!(*error.kind, ErrorKind::IterationLimitExceeded(100));
+  assert_eq!(computer.compute(&ast::from_string("i")), Ok(Value::Int(100)));
+
+  assert_eq!(
+    computer.compute(&ast::from_string("for i in 0..100 {}")),
+    Ok(Value::Unit)
+  );
+  assert!(computer.compute(&ast::from_string("for i in 0..101 {}")).is_err());
+}
+
+#[test]
+fn variables_are_sorted() {
+  let mut computer = Computer::new();
+  computer.compute(&ast::from_string("b = 2; a = 1; fn f(x) = x")).unwrap();
+
+  let names: Vec<_> = computer.variables().into_iter().map(|(name, _)| name).collect();
+  assert_eq!(names, ["a", "b", "f"]);
+}
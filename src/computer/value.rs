@@ -0,0 +1,151 @@
+use std::{
+  cell::RefCell,
+  fmt::Display,
+  rc::{Rc, Weak},
+};
+
+use crate::{ast::Ast, stack::MAX_LIST_DEPTH};
+
+use super::{
+  builtins::Builtin,
+  error::ErrorKind,
+  scope::{Scope, ScopeRef},
+};
+
+#[derive(Clone)]
+pub struct Function {
+  pub name: Option<String>,
+  pub params: Vec<String>,
+  pub body: Rc<Ast>,
+  /// Scope the function was defined in
+  pub closure: Closure,
+}
+
+/// A function stored in the scope it was defined in, like any named function, would keep that scope alive
+/// while the scope keeps the function alive, so neither would ever be freed. Such a function holds the scope
+/// weakly, and `Scope` hands out strongly held copies of it. Other cycles, like a list of functions stored in the
+/// scope they were defined in, are only broken for the globals, which `Computer` empties when it is dropped.
+#[derive(Clone)]
+pub enum Closure {
+  Strong(ScopeRef),
+  Weak(Weak<RefCell<Scope>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  Unit,
+  Bool(bool),
+  Int(i128),
+  Float(f64),
+  Char(char),
+  String(String),
+  Range(i128, i128),
+  /// Items and how deeply lists are nested in them, counting the list itself
+  List(Rc<Vec<Value>>, usize),
+  Function(Rc<Function>),
+  Builtin(&'static Builtin),
+}
+
+// the closure may contain the function itself, so it is left out
+impl std::fmt::Debug for Function {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Function")
+      .field("name", &self.name)
+      .field("params", &self.params)
+      .field("body", &self.body)
+      .finish_non_exhaustive()
+  }
+}
+
+/// Functions are only equal to themselves, that is to functions with the same body defined in the same scope
+impl PartialEq for Function {
+  fn eq(&self, other: &Self) -> bool {
+    Rc::ptr_eq(&self.body, &other.body) && self.closure.as_ptr() == other.closure.as_ptr()
+  }
+}
+
+impl Display for Function {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match &self.name {
+      Some(name) => write!(f, "<fn {}({})>", name, self.params.join(", ")),
+      None => write!(f, "<fn({})>", self.params.join(", ")),
+    }
+  }
+}
+
+impl Function {
+  pub fn name(&self) -> &str {
+    self.name.as_deref().unwrap_or("<fn>")
+  }
+
+  /// Returns the scope the function was defined in
+  pub fn scope(&self) -> ScopeRef {
+    match &self.closure {
+      Closure::Strong(scope) => scope.clone(),
+      // the function is only held weakly inside the scope, so the scope is alive whenever the function is seen
+      Closure::Weak(scope) => scope.upgrade().expect("scope outlives the functions stored in it"),
+    }
+  }
+}
+
+impl Closure {
+  fn as_ptr(&self) -> *const RefCell<Scope> {
+    match self {
+      Closure::Strong(scope) => Rc::as_ptr(scope),
+      Closure::Weak(scope) => scope.as_ptr(),
+    }
+  }
+}
+
+impl Display for Value {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Value::Unit => write!(f, "null"),
+      Value::Bool(b) => write!(f, "{}", b),
+      Value::Int(i) => write!(f, "{}", i),
+      Value::Float(n) => write!(f, "{:?}", n),
+      Value::Char(c) => write!(f, "{:?}", c),
+      Value::String(s) => write!(f, "{:?}", s),
+      Value::Range(start, end) => write!(f, "{}..{}", start, end),
+      Value::List(items, _) => {
+        let items: Vec<_> = items.iter().map(|item| item.to_string()).collect();
+        write!(f, "[{}]", items.join(", "))
+      }
+      Value::Function(function) => write!(f, "{}", function),
+      Value::Builtin(builtin) => write!(f, "<builtin {}>", builtin.name),
+    }
+  }
+}
+
+impl Value {
+  /// Makes a list of the items, unless that nests lists deeper than `MAX_LIST_DEPTH`
+  pub fn list(items: Vec<Value>) -> Result<Value, ErrorKind> {
+    let depth = 1 + items.iter().map(Value::depth).max().unwrap_or(0);
+    if depth > MAX_LIST_DEPTH {
+      return Err(ErrorKind::ListNestedTooDeeply(MAX_LIST_DEPTH));
+    }
+
+    Ok(Value::List(Rc::new(items), depth))
+  }
+
+  fn depth(&self) -> usize {
+    match self {
+      Value::List(_, depth) => *depth,
+      _ => 0,
+    }
+  }
+
+  pub fn type_name(&self) -> &'static str {
+    match self {
+      Value::Unit => "null",
+      Value::Bool(_) => "bool",
+      Value::Int(_) => "int",
+      Value::Float(_) => "float",
+      Value::Char(_) => "char",
+      Value::String(_) => "string",
+      Value::Range(..) => "range",
+      Value::List(..) => "list",
+      Value::Function(_) | Value::Builtin(_) => "function",
+    }
+  }
+}
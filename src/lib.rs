@@ -0,0 +1,9 @@
+#![allow(dead_code)]
+
+#[allow(clippy::module_inception)]
+pub mod ast;
+pub mod computer;
+pub mod source_code;
+pub mod stack;
+#[allow(clippy::module_inception)]
+pub mod tokeniser;
@@ -1,49 +1,142 @@
-#![allow(dead_code)]
+use std::{
+  env,
+  io::{stdin, stdout, ErrorKind, Write},
+  process::ExitCode,
+};
 
-use std::io::{stdin, stdout, Write};
+use nolang::{
+  ast::{self, Value},
+  computer::Computer,
+  source_code::{SourceCode, SourceMap},
+  stack,
+};
 
-use computer::Computer;
+fn run_file(filename: String) -> ExitCode {
+  let mut source_map = SourceMap::new();
+  let file = match SourceCode::from_file(filename.clone()) {
+    Ok(source_code) => source_map.add(source_code),
+    Err(e) => {
+      eprintln!("{}: {}", filename, e);
+      return ExitCode::FAILURE;
+    }
+  };
+
+  let mut computer = Computer::new();
+
+  let (program, diagnostics) = ast::parse(&source_map[file]);
 
-mod ast;
-mod computer;
-mod source_code;
-mod tokeniser;
+  if !diagnostics.is_empty() {
+    for diagnostic in diagnostics {
+      eprint!(
+        "{}",
+        source_map.render_error(&diagnostic.code, diagnostic.start, diagnostic.end)
+      );
+    }
+    return ExitCode::FAILURE;
+  }
 
-fn main() {
+  let lines = match &program.value {
+    Value::Program(lines) => lines.as_slice(),
+    _ => std::slice::from_ref(&program),
+  };
+
+  for line in lines {
+    match computer.compute(line) {
+      Ok(result) => {
+        if !matches!(
+          line.value,
+          Value::Assignment(..)
+            | Value::Declaration(..)
+            | Value::CompoundAssignment(..)
+            | Value::FunctionDefinition(..)
+            | Value::While(..)
+            | Value::For(..)
+        ) {
+          println!("{}", result)
+        }
+      }
+      Err(e) => {
+        eprint!("{}", source_map.render_error(&e.kind, e.start, e.end));
+        return ExitCode::FAILURE;
+      }
+    }
+  }
+
+  ExitCode::SUCCESS
+}
+
+fn run_repl() {
   println!("Enter the expression (l or list to list all variables, q or quit to exit)");
 
   let mut computer = Computer::new();
+  // every line is kept as a separate file, so that errors in functions defined earlier point to their line
+  let mut source_map = SourceMap::new();
+  let mut number = 0;
 
   loop {
     print!("> ");
-    stdout().flush().unwrap();
+    if stdout().flush().is_err() {
+      break;
+    }
 
     let mut code = String::new();
-    let res = stdin().read_line(&mut code).expect("Cannot read from stdin, exiting");
-
-    if res == 0 {
-      break;
+    match stdin().read_line(&mut code) {
+      Ok(0) => break,
+      Ok(_) => {}
+      // the broken line is already consumed, so the next one can still be read
+      Err(e) if e.kind() == ErrorKind::InvalidData => {
+        println!("error: {}", e);
+        continue;
+      }
+      Err(e) => {
+        eprintln!("error: cannot read from stdin: {}", e);
+        break;
+      }
     }
 
     let code = code.trim();
 
-    if code.len() < 1 {
+    if code.is_empty() {
       continue;
     } else if code == "q" || code == "quit" {
       break;
     } else if code == "l" || code == "list" {
-      for (k, v) in computer.iter() {
+      for (k, v) in computer.variables() {
         println!("{} = {}", k, v)
       }
       continue;
     }
 
-    let ast = ast::from_string(code);
+    number += 1;
+    let file = source_map.add(SourceCode::with_name(format!("<line {}>", number), code.to_string()));
+    let (ast, diagnostics) = ast::parse(&source_map[file]);
+
+    if !diagnostics.is_empty() {
+      for diagnostic in diagnostics {
+        print!(
+          "{}",
+          source_map.render_error(&diagnostic.code, diagnostic.start, diagnostic.end)
+        );
+      }
+      continue;
+    }
+
     let result = computer.compute(&ast);
 
     match result {
       Ok(result) => println!("{}", result),
-      Err(e) => println!("Error: {}", e),
+      Err(e) => print!("{}", source_map.render_error(&e.kind, e.start, e.end)),
     };
   }
 }
+
+fn main() -> ExitCode {
+  // deeply nested code needs more stack than the main thread has
+  match env::args().nth(1) {
+    Some(filename) => stack::run(|| run_file(filename)),
+    None => {
+      stack::run(run_repl);
+      ExitCode::SUCCESS
+    }
+  }
+}
@@ -1,18 +1,45 @@
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
+use super::FileId;
+
+#[derive(Debug, Default, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
 pub struct Location {
-  pub position: usize,
+  pub file: FileId,
+  /// Offset in bytes from the start of the code, which can be used to slice it
+  pub byte: usize,
   pub line: usize,
-  pub offset: usize,
+  /// Number of chars before the location in its line
+  pub column: usize,
+  /// Number of UTF-16 code units before the location in its line, as editors count them
+  pub utf16_column: usize,
 }
 
 impl std::fmt::Display for Location {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "{}:{}", self.line, self.offset)
+    write!(f, "{}:{}", self.line + 1, self.column + 1)
   }
 }
 
 impl Location {
-  pub fn new(position: usize, line: usize, offset: usize) -> Location {
-    Location { line, offset, position }
+  pub fn new(file: FileId, byte: usize, line: usize, column: usize, utf16_column: usize) -> Location {
+    Location {
+      file,
+      byte,
+      line,
+      column,
+      utf16_column,
+    }
+  }
+
+  /// Moves the location past the char
+  pub fn advance(&mut self, c: char) {
+    self.byte += c.len_utf8();
+
+    if c == '\n' {
+      self.line += 1;
+      self.column = 0;
+      self.utf16_column = 0;
+    } else {
+      self.column += 1;
+      self.utf16_column += c.len_utf16();
+    }
   }
 }
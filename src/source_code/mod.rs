@@ -1,77 +1,133 @@
 pub mod location;
+pub mod source_map;
 
 pub use location::*;
-use std::{fs::File, io::Read};
+pub use source_map::*;
+use std::{fmt::Display, fs::File, io::Read};
 
 #[derive(Debug)]
 pub struct SourceCode {
+  pub name: String,
   pub code: String,
+  file: FileId,
+  /// Byte offsets of the lines following line breaks
   line_breaks: Vec<usize>,
 }
 
 impl SourceCode {
   pub fn new(code: String) -> SourceCode {
+    SourceCode::with_name("<string>".to_string(), code)
+  }
+
+  pub fn with_name(name: String, code: String) -> SourceCode {
     let mut lines = vec![];
-    let mut position: usize = 0;
 
-    for ch in code.chars() {
+    for (position, ch) in code.char_indices() {
       if ch == '\n' {
         lines.push(position + 1);
       }
-
-      position += 1;
     }
 
     SourceCode {
+      name,
       code,
+      file: FileId::UNMAPPED,
       line_breaks: lines,
     }
   }
 
   pub fn from_file(filename: String) -> Result<SourceCode, std::io::Error> {
-    let mut file = File::open(filename)?;
+    let mut file = File::open(&filename)?;
 
     let mut contents = String::new();
     file.read_to_string(&mut contents)?;
 
-    Ok(SourceCode::new(contents))
+    Ok(SourceCode::with_name(filename, contents))
   }
 
-  pub fn to_location(&self, position: usize) -> Option<Location> {
-    if position > self.code.len() {
-      return None;
-    }
+  /// Identifier of the file in the source map, which locations in the code carry.
+  /// It is `FileId::UNMAPPED` until the code is added to a map
+  pub fn file(&self) -> FileId {
+    self.file
+  }
 
+  /// Finds the location of a byte offset, which has to be at a char boundary
+  pub fn to_location(&self, position: usize) -> Option<Location> {
     let line = match self.line_breaks.binary_search(&position) {
       Ok(n) => n + 1,
       Err(n) => n,
     };
 
     let lb = if line == 0 { 0 } else { self.line_breaks[line - 1] };
-
-    Some(Location::new(position, line, position - lb))
+    let before = self.code.get(lb..position)?;
+
+    Some(Location::new(
+      self.file,
+      position,
+      line,
+      before.chars().count(),
+      before.encode_utf16().count(),
+    ))
   }
 
   pub fn get_line(&self, line: usize) -> Option<String> {
     let line_start = if line == 0 { 0 } else { *self.line_breaks.get(line - 1)? };
     let line_end = self.line_breaks.get(line).map_or(self.code.len(), |e| *e);
 
-    Some(self.code[line_start..line_end].trim().to_string())
+    Some(self.code.get(line_start..line_end)?.trim_end_matches(['\r', '\n']).to_string())
   }
 
-  pub fn get_code_range(&self, from: usize, to: usize) -> Option<String> {
-    let len = self.code.len();
-    if from > len || to > len || to < from {
-      None
-    } else {
-      Some(self.code[from..to].to_string())
+  /// Renders an error message followed by the offending line with the `start..end` range underlined
+  ///
+  /// # Examples
+  ///
+  /// ```text
+  /// error: variable `b` not found
+  ///  --> script.nl:2:5
+  ///   |
+  /// 2 | a + b
+  ///   |     ^
+  /// ```
+  pub fn render_error(&self, message: impl Display, start: Location, end: Location) -> String {
+    let mut result = format!("error: {}\n", message);
+    let number = (start.line + 1).to_string();
+    let gutter = " ".repeat(number.len());
+
+    result += &format!("{}--> {}:{}\n", gutter, self.name, start);
+
+    if let Some(line) = self.get_line(start.line) {
+      let length = line.chars().count();
+      let underline_end = if end.line == start.line {
+        end.column.min(length)
+      } else {
+        length
+      };
+      let underline = "^".repeat(underline_end.saturating_sub(start.column).max(1));
+      let padding: String = line
+        .chars()
+        .chain(std::iter::repeat(' '))
+        .take(start.column)
+        .map(|c| if c == '\t' { c } else { ' ' })
+        .collect();
+
+      result += &format!(
+        "{} |\n{} | {}\n{} | {}{}\n",
+        gutter, number, line, gutter, padding, underline
+      );
     }
+
+    result
+  }
+
+  /// Returns the code between two byte offsets, if both are at char boundaries
+  pub fn get_code_range(&self, from: usize, to: usize) -> Option<String> {
+    self.code.get(from..to).map(str::to_string)
   }
 }
 
 #[cfg(test)]
 mod test {
-  use super::SourceCode;
+  use super::{FileId, Location, SourceCode};
 
   #[test]
   fn creates_source_code() {
@@ -100,6 +156,39 @@ mod test {
     assert_eq!(sc.get_line(3), Some("line 3".to_string()));
   }
 
+  #[test]
+  fn keeps_indentation_of_lines() {
+    let code = "line 1\n  line 2\r\n".to_string();
+    let sc = SourceCode::new(code);
+
+    assert_eq!(sc.get_line(1), Some("  line 2".to_string()));
+  }
+
+  #[test]
+  fn renders_errors() {
+    let code = "a = 1\n\tb + cd\n".to_string();
+    let sc = SourceCode::with_name("test.nl".to_string(), code);
+    let start = sc.to_location(11).unwrap();
+    let end = sc.to_location(13).unwrap();
+
+    assert_eq!(
+      sc.render_error("variable `cd` not found", start, end),
+      "error: variable `cd` not found\n --> test.nl:2:6\n  |\n2 | \tb + cd\n  | \t    ^^\n"
+    );
+  }
+
+  #[test]
+  fn renders_errors_at_the_end_of_code() {
+    let code = "a +".to_string();
+    let sc = SourceCode::with_name("test.nl".to_string(), code);
+    let location = sc.to_location(3).unwrap();
+
+    assert_eq!(
+      sc.render_error("Expected Token", location, location),
+      "error: Expected Token\n --> test.nl:1:4\n  |\n1 | a +\n  |    ^\n"
+    );
+  }
+
   #[test]
   fn returns_code_ranges() {
     let code = "line 1\nline 2\n\nline 3".to_string();
@@ -107,4 +196,42 @@ mod test {
 
     assert_eq!(sc.get_code_range(3, 8), Some("e 1\nl".to_string()));
   }
+
+  #[test]
+  fn locates_non_ascii_code() {
+    let code = "'試' + 😀\nü".to_string();
+    let sc = SourceCode::new(code);
+
+    assert_eq!(sc.to_location(5), Some(Location::new(FileId::default(), 5, 0, 3, 3)));
+    assert_eq!(sc.to_location(12), Some(Location::new(FileId::default(), 12, 0, 7, 8)));
+    assert_eq!(sc.to_location(13), Some(Location::new(FileId::default(), 13, 1, 0, 0)));
+    assert_eq!(sc.to_location(15), Some(Location::new(FileId::default(), 15, 1, 1, 1)));
+    assert_eq!(sc.to_location(2), None);
+    assert_eq!(sc.to_location(16), None);
+  }
+
+  #[test]
+  fn returns_non_ascii_lines_and_ranges() {
+    let code = "'試'\nü = 1".to_string();
+    let sc = SourceCode::new(code);
+
+    assert_eq!(sc.get_line(1), Some("ü = 1".to_string()));
+    assert_eq!(sc.get_line(2), None);
+    assert_eq!(sc.get_code_range(1, 4), Some("試".to_string()));
+    assert_eq!(sc.get_code_range(2, 4), None);
+    assert_eq!(sc.get_code_range(4, 1), None);
+  }
+
+  #[test]
+  fn renders_errors_after_non_ascii_code() {
+    let code = "'試' + ab".to_string();
+    let sc = SourceCode::with_name("test.nl".to_string(), code);
+    let start = sc.to_location(8).unwrap();
+    let end = sc.to_location(10).unwrap();
+
+    assert_eq!(
+      sc.render_error("variable `ab` not found", start, end),
+      "error: variable `ab` not found\n --> test.nl:1:7\n  |\n1 | '試' + ab\n  |       ^^\n"
+    );
+  }
 }
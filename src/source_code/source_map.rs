@@ -0,0 +1,142 @@
+use std::{fmt::Display, ops::Index};
+
+use super::{Location, SourceCode};
+
+/// Identifies a file in the source map, so that locations can tell which file they point into
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Clone, Copy)]
+pub struct FileId(usize);
+
+impl FileId {
+  /// Carried by code which was never added to a source map, so it cannot be mistaken for a file in one
+  pub const UNMAPPED: FileId = FileId(usize::MAX);
+}
+
+impl Default for FileId {
+  fn default() -> Self {
+    FileId::UNMAPPED
+  }
+}
+
+/// Keeps all loaded code, so that diagnostics can be rendered from the right file
+#[derive(Debug, Default)]
+pub struct SourceMap {
+  files: Vec<SourceCode>,
+}
+
+/// Location displayed together with the name of its file, as `file:line:col`
+pub struct FileLocation<'a> {
+  name: &'a str,
+  location: Location,
+}
+
+impl Display for FileLocation<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}:{}", self.name, self.location)
+  }
+}
+
+impl SourceMap {
+  pub fn new() -> SourceMap {
+    SourceMap { files: vec![] }
+  }
+
+  /// Adds the code to the map, giving it the identifier which its locations will carry
+  pub fn add(&mut self, mut source_code: SourceCode) -> FileId {
+    let file = FileId(self.files.len());
+
+    source_code.file = file;
+    self.files.push(source_code);
+
+    file
+  }
+
+  pub fn get(&self, file: FileId) -> Option<&SourceCode> {
+    self.files.get(file.0)
+  }
+
+  pub fn locate(&self, location: Location) -> FileLocation<'_> {
+    let name = self.get(location.file).map_or("<unknown>", |source_code| &source_code.name);
+
+    FileLocation { name, location }
+  }
+
+  /// Describes what happened at the location as `file:line:col: message`
+  pub fn describe(&self, location: Location, message: impl Display) -> String {
+    format!("{}: {}", self.locate(location), message)
+  }
+
+  /// Renders an error in the file the `start` location points into
+  pub fn render_error(&self, message: impl Display, start: Location, end: Location) -> String {
+    match self.get(start.file) {
+      Some(source_code) => source_code.render_error(message, start, end),
+      None => format!("error: {}\n --> {}\n", message, self.locate(start)),
+    }
+  }
+}
+
+impl Index<FileId> for SourceMap {
+  type Output = SourceCode;
+
+  fn index(&self, file: FileId) -> &SourceCode {
+    &self.files[file.0]
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{FileId, SourceMap};
+  use crate::source_code::SourceCode;
+
+  #[test]
+  fn gives_every_file_its_own_identifier() {
+    let mut map = SourceMap::new();
+    let first = map.add(SourceCode::with_name("first.nl".to_string(), "a".to_string()));
+    let second = map.add(SourceCode::with_name("second.nl".to_string(), "b".to_string()));
+
+    assert_ne!(first, second);
+    assert_eq!(map[second].name, "second.nl");
+    assert_eq!(map[second].file(), second);
+    assert_eq!(map.get(FileId(2)).map(|source_code| source_code.file()), None);
+  }
+
+  #[test]
+  fn keeps_code_outside_the_map_apart() {
+    let mut map = SourceMap::new();
+    let outside = SourceCode::with_name("outside.nl".to_string(), "a + b".to_string());
+    let first = map.add(SourceCode::with_name("first.nl".to_string(), "c + d".to_string()));
+    let location = outside.to_location(4).unwrap();
+
+    assert_eq!(outside.file(), FileId::UNMAPPED);
+    assert_ne!(outside.file(), first);
+    assert_eq!(
+      map.describe(location, "variable `b` not found"),
+      "<unknown>:1:5: variable `b` not found"
+    );
+  }
+
+  #[test]
+  fn locates_code_in_files() {
+    let mut map = SourceMap::new();
+    map.add(SourceCode::with_name("first.nl".to_string(), "a".to_string()));
+    let second = map.add(SourceCode::with_name("second.nl".to_string(), "b\nc + d".to_string()));
+    let location = map[second].to_location(6).unwrap();
+
+    assert_eq!(location.file, second);
+    assert_eq!(map.locate(location).to_string(), "second.nl:2:5");
+    assert_eq!(map.describe(location, "error"), "second.nl:2:5: error");
+  }
+
+  #[test]
+  fn renders_errors_from_the_right_file() {
+    let mut map = SourceMap::new();
+    let first = map.add(SourceCode::with_name("first.nl".to_string(), "a + b".to_string()));
+    map.add(SourceCode::with_name("second.nl".to_string(), "c".to_string()));
+    let start = map[first].to_location(4).unwrap();
+    let end = map[first].to_location(5).unwrap();
+
+    assert_eq!(
+      map.render_error("variable `b` not found", start, end),
+      "error: variable `b` not found\n --> first.nl:1:5\n  |\n1 | a + b\n  |     ^\n"
+    );
+  }
+}
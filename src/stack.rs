@@ -0,0 +1,32 @@
+//! How much stack parsing and computing get, and the depth limits that keep them within it.
+//!
+//! Both recurse once for every level of nesting, so each limit is the number of levels that fits into `STACK_SIZE`
+//! with room to spare. The stack a level takes is measured in debug builds, which need several times more than release ones.
+
+use std::{panic, thread};
+
+/// Stack of the thread that parses and computes code, of which only the part in use takes memory
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// How deeply code can be nested, counting brackets, blocks, prefix and right associative operators.
+/// Chains like `a + b + c` are parsed in a loop, so they do not count. A level takes up to 10 KB, which is 10 MB in all.
+pub const MAX_PARSE_DEPTH: usize = 1000;
+
+/// How deeply computations can nest, counting the ones of every function being called.
+/// A level takes up to 8 KB when functions are called through builtins like `map`, so all of them take about 80 MB.
+pub const MAX_NESTING: usize = 10_000;
+
+/// How deeply lists can be nested, so that printing, comparing and dropping them stays far below the stack size
+pub const MAX_LIST_DEPTH: usize = 256;
+
+/// Runs `f` on a thread with `STACK_SIZE` of stack and returns its result, passing its panics on
+pub fn run<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+  thread::scope(|scope| {
+    let thread = thread::Builder::new()
+      .stack_size(STACK_SIZE)
+      .spawn_scoped(scope, f)
+      .expect("cannot start a thread for the computation");
+
+    thread.join().unwrap_or_else(|payload| panic::resume_unwind(payload))
+  })
+}
@@ -1,13 +1,15 @@
 use super::trie::Trie;
 use once_cell::sync::Lazy;
 
-pub static OP_LIST: Lazy<Trie> = Lazy::new(|| Trie::from_op_list());
+pub static OP_LIST: Lazy<Trie> = Lazy::new(Trie::from_op_list);
+pub static KEYWORDS: Lazy<Trie> = Lazy::new(Trie::from_keyword_list);
 
 pub fn is_bracket(c: char) -> bool {
-  match c {
-    '(' | ')' | '[' | ']' | '{' | '}' => true,
-    _ => false,
-  }
+  matches!(c, '(' | ')' | '[' | ']' | '{' | '}')
+}
+
+pub fn is_keyword(word: &str) -> bool {
+  KEYWORDS.has(word)
 }
 
 pub fn is_punctuation(c: char) -> bool {
@@ -15,5 +17,9 @@ pub fn is_punctuation(c: char) -> bool {
 }
 
 pub fn is_punctuation_or_whitespace(c: char) -> bool {
-  return c.is_whitespace() || is_punctuation(c);
+  c.is_whitespace() || is_punctuation(c)
+}
+
+pub fn is_token_boundary(c: char) -> bool {
+  is_bracket(c) || is_punctuation_or_whitespace(c)
 }
@@ -9,17 +9,11 @@ mod trie;
 
 use crate::source_code::SourceCode;
 
+pub use self::tokeniser::Tokeniser;
 pub use token::{ErrorCode, Token, TokenValue};
 
-#[cfg(test)]
-pub use token::generators;
-
-use self::tokeniser::Tokeniser;
-
-pub fn from_source_code<'a>(source_code: &'a SourceCode) -> Vec<Token> {
-  let mut tokeniser = Tokeniser::new(source_code);
-
-  tokeniser.parse()
+pub fn from_source_code(source_code: &SourceCode) -> Vec<Token> {
+  Tokeniser::new(source_code).collect()
 }
 
 pub fn from_string(code: &str) -> Vec<Token> {
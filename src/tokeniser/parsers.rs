@@ -1,11 +1,14 @@
-use std::str::FromStr;
+use std::{num::IntErrorKind, str::FromStr};
 
 use super::token::{ErrorCode, TokenValue};
 
+/// Parses the symbol starting at the byte position, returning it with the position of the next one
 pub fn parse_symbol_in_string(s: &str, pos: usize, parse_unicode: bool) -> Result<(char, usize), ErrorCode> {
-  match s.chars().nth(pos) {
+  let mut chars = s.get(pos..).ok_or(ErrorCode::BrokenStringLiteral)?.chars();
+
+  match chars.next() {
     None => Err(ErrorCode::BrokenStringLiteral),
-    Some('\\') => match s.chars().nth(pos + 1) {
+    Some('\\') => match chars.next() {
       None => Err(ErrorCode::UnterminatedCharLiteral),
       Some(c) => match c {
         'n' => Ok(('\n', pos + 2)),
@@ -13,18 +16,18 @@ pub fn parse_symbol_in_string(s: &str, pos: usize, parse_unicode: bool) -> Resul
         't' => Ok(('\t', pos + 2)),
         '\'' => Ok(('\'', pos + 2)),
         '\\' => Ok(('\\', pos + 2)),
-        'u' if !parse_unicode || s.len() < pos + 6 => Err(ErrorCode::BrokenUnicodeSequence),
-        'u' => match u32::from_str_radix(&s[pos + 2..pos + 6], 16) {
-          Err(_) => Err(ErrorCode::BrokenUnicodeSequence),
-          Ok(u) => match char::from_u32(u) {
+        'u' if !parse_unicode => Err(ErrorCode::BrokenUnicodeSequence),
+        'u' => match s.get(pos + 2..pos + 6).map(|code| u32::from_str_radix(code, 16)) {
+          Some(Ok(u)) => match char::from_u32(u) {
             Some(c) => Ok((c, pos + 6)),
             None => Err(ErrorCode::BrokenUnicodeSequence),
           },
+          _ => Err(ErrorCode::BrokenUnicodeSequence),
         },
         _ => Err(ErrorCode::UnknownEscapeSequence),
       },
     },
-    Some(c) => Ok((c, pos + 1)),
+    Some(c) => Ok((c, pos + c.len_utf8())),
   }
 }
 
@@ -47,18 +50,47 @@ pub fn parse_string(s: String) -> Result<String, ErrorCode> {
   Ok(result)
 }
 
-pub fn parse_int(s: String, radix: u32) -> TokenValue {
-  let src = if radix == 16 { s.trim_start_matches("0x") } else { &s };
+/// Removes the radix prefix and digit separators, which are not accepted by the standard parsers
+fn digits(s: &str, radix: u32) -> String {
+  let prefix = match radix {
+    2 => "0b",
+    8 => "0o",
+    16 => "0x",
+    _ => "",
+  };
+
+  s.strip_prefix(prefix).unwrap_or(s).replace('_', "")
+}
 
-  match u128::from_str_radix(src, radix) {
+pub fn parse_int(s: String, radix: u32) -> TokenValue {
+  match u128::from_str_radix(&digits(&s, radix), radix) {
     Ok(n) => TokenValue::IntLiteral(n),
-    _ => TokenValue::Error(s, ErrorCode::IntLiteralTooLong),
+    Err(e) if *e.kind() == IntErrorKind::PosOverflow => TokenValue::Error(s, ErrorCode::IntLiteralTooLong),
+    // leading zero octal literals can still contain decimal digits, like `09`
+    Err(_) => TokenValue::Error(s, ErrorCode::MalformedNumberLiteral),
   }
 }
 
 pub fn parse_float(s: String) -> TokenValue {
-  match f64::from_str(&s) {
+  match f64::from_str(&digits(&s, 10)) {
     Ok(n) => TokenValue::FloatLiteral(n),
     _ => TokenValue::Error(s, ErrorCode::FloatLiteralTooLong),
   }
 }
+
+/// Parses a hex float like `0x1.8p3`, which is `0x1.8` multiplied by 2 to the power of 3
+pub fn parse_hex_float(s: String) -> TokenValue {
+  let digits = digits(&s, 16);
+  let (mantissa, exponent) = digits.split_once(['p', 'P']).unwrap_or((&digits, ""));
+  let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+  let mantissa = u128::from_str_radix(&format!("{}{}", integer, fraction), 16).ok();
+  let exponent = i32::from_str(exponent)
+    .ok()
+    .and_then(|exponent| exponent.checked_sub(4 * i32::try_from(fraction.len()).ok()?));
+
+  match (mantissa, exponent) {
+    (Some(mantissa), Some(exponent)) => TokenValue::FloatLiteral(mantissa as f64 * 2f64.powi(exponent)),
+    _ => TokenValue::Error(s, ErrorCode::FloatLiteralTooLong),
+  }
+}
@@ -1,4 +1,5 @@
-use super::{from_string, token::generators::*, token::ErrorCode};
+use super::{from_string, token::generators::*, token::ErrorCode, Tokeniser};
+use crate::source_code::SourceCode;
 
 macro_rules! test {
   ( $name: ident, $code: expr, { $($index: expr => $token: expr),* } ) => {
@@ -27,7 +28,21 @@ macro_rules! test {
 
 test!(identifier_single, "test", { 0 => ident!("test") });
 test!(identifier_multiple, "test success", 2, { 0 => ident!("test"), 1 => ident!("success") });
-test!(identifier_with_error, "1test", { 0 => error!("1test", ErrorCode::UnexpectedToken) });
+
+test!(keyword_single, "while", 1, { 0 => keyword!("while") });
+test!(keyword_among_identifiers, "for item in items", 4, {
+  0 => keyword!("for"),
+  1 => ident!("item"),
+  2 => keyword!("in"),
+  3 => ident!("items")
+});
+test!(keyword_prefix_is_identifier, "iffy format null_ trues", 4, {
+  0 => ident!("iffy"),
+  1 => ident!("format"),
+  2 => ident!("null_"),
+  3 => ident!("trues")
+});
+test!(keyword_literals, "true false null", 3, { 0 => keyword!("true"), 1 => keyword!("false"), 2 => keyword!("null") });
 
 test!(char_single, "'c'", { 0 => char!('c') });
 test!(char_multiple, r"'c' '\n' '\u0041'", 3, { 0 => char!('c'), 1 => char!('\n'), 2 => char!('A') });
@@ -43,26 +58,76 @@ test!(string_single, r#""test""#, { 0 => string!("test") });
 test!(string_unfinished_with_eof, r#""test"#, { 0 => error!("\"test", ErrorCode::UnterminatedStringLiteral) });
 test!(string_unfinished_with_newline, "\"test\n\"test\"", 2, { 0 => error!("\"test", ErrorCode::UnterminatedStringLiteral), 1 => string!("test") });
 test!(string_multiple, &r#""""test test""""#.replace(' ', "\n"), { 0 => string!("test\ntest") });
+test!(string_empty, r#""""#, 1, { 0 => string!("") });
+test!(string_followed_by_tokens, r#""ab" + "" + """c""" x"#, 6, {
+  0 => string!("ab"),
+  1 => operator!("+"),
+  2 => string!(""),
+  3 => operator!("+"),
+  4 => string!("c"),
+  5 => ident!("x")
+});
 test!(string_with_escape_sequences, r#""\n\t\u0041""#, { 0 => string!("\n\tA") });
+test!(string_with_unicode_symbols, r#""試😀\u0041é""#, 1, { 0 => string!("試😀Aé") });
+test!(string_with_broken_unicode_escape, r#""\u00試""#, 1, { 0 => error!(r#""\u00試""#, ErrorCode::BrokenUnicodeSequence) });
 
 test!(int_decimal_zero, "0", { 0 => int!(0) });
 test!(int_decimal_single, "42", { 0 => int!(42) });
 test!(int_very_long, "340282366920938463463374607431768211455", { 0 => int!(340282366920938463463374607431768211455) });
 test!(int_too_long_error, "340282366920938463463374607431768211456", { 0 => error!("340282366920938463463374607431768211456", ErrorCode::IntLiteralTooLong) });
+test!(int_with_trailing_letters_error, "1test", 1, { 0 => error!("1test", ErrorCode::MalformedNumberLiteral) });
 test!(int_octal, "0100", { 0 => int!(64) });
 test!(int_hex_zero, "0x0", { 0 => int!(0) });
 test!(int_hex, "0x7f", { 0 => int!(127) });
+test!(int_hex_leading_zero, "0x0f", 1, { 0 => int!(15) });
+test!(int_binary, "0b101", 1, { 0 => int!(5) });
+test!(int_explicit_octal, "0o17", 1, { 0 => int!(15) });
+test!(int_with_separators, "1_000_000 0xff_ff 0b1_0", 3, { 0 => int!(1_000_000), 1 => int!(0xffff), 2 => int!(2) });
+test!(int_octal_with_decimal_digit, "09", 1, { 0 => error!("09", ErrorCode::MalformedNumberLiteral) });
+test!(int_binary_with_wrong_digit, "0b102 + 1", 3, { 0 => error!("0b102", ErrorCode::MalformedNumberLiteral), 2 => int!(1) });
+test!(int_prefix_without_digits, "0x 0o", 2, {
+  0 => error!("0x", ErrorCode::MalformedNumberLiteral),
+  1 => error!("0o", ErrorCode::MalformedNumberLiteral)
+});
+test!(int_with_broken_separators, "1_ 1__0 0x_1", 3, {
+  0 => error!("1_", ErrorCode::MalformedNumberLiteral),
+  1 => error!("1__0", ErrorCode::MalformedNumberLiteral),
+  2 => error!("0x_1", ErrorCode::MalformedNumberLiteral)
+});
+test!(int_with_unknown_prefix, "0z", 1, { 0 => error!("0z", ErrorCode::MalformedNumberLiteral) });
 test!(int_multiple, "5 7", {0 =>  int!(5), 1 => int!(7) });
 test!(int_divided_by_operators, "5+7", { 0 => int!(5), 2 => int!(7) });
 
+test!(int_in_brackets, "(0)(7)(0x7)(07)", 12, { 1 => int!(0), 4 => int!(7), 7 => int!(7), 10 => int!(7) });
+test!(float_in_brackets, "(2.5)", 3, { 1 => float!(2.5), 2 => bracket!(')') });
+test!(float_with_error, "2.5x", 1, { 0 => error!("2.5x", ErrorCode::MalformedNumberLiteral) });
 test!(float_single, "7123.5514", { 0 => float!(7123.5514) });
 test!(float_without_integer_part, ".45", { 0 => float!(0.45) });
 test!(float_without_floating_part, "42.", { 0 => float!(42.0) });
+test!(float_with_exponent, "6.02e23 1E-9 2e+3 .5e1 1.e2", 5, {
+  0 => float!(6.02e23),
+  1 => float!(1e-9),
+  2 => float!(2e3),
+  3 => float!(5.0),
+  4 => float!(100.0)
+});
+test!(float_with_separators, "1_000.000_1", 1, { 0 => float!(1000.0001) });
+test!(float_with_empty_exponent, "1e+ 2", 2, { 0 => error!("1e+", ErrorCode::MalformedNumberLiteral), 1 => int!(2) });
+test!(float_hex, "0x1.8p3 0x1p-2 0xAp0", 3, { 0 => float!(12.0), 1 => float!(0.25), 2 => float!(10.0) });
+test!(float_hex_without_exponent, "0x1.8", 1, { 0 => error!("0x1.8", ErrorCode::MalformedNumberLiteral) });
+test!(float_hex_with_too_big_exponent, "0x1p99999999999", 1, { 0 => error!("0x1p99999999999", ErrorCode::FloatLiteralTooLong) });
 
 test!(operator_dot, ".", { 0 => operator!(".") });
+test!(operator_range, "..", 1, { 0 => operator!("..") });
+test!(operator_range_between_ints, "1..05", 3, { 0 => int!(1), 1 => operator!(".."), 2 => int!(5) });
+test!(operator_range_after_zero, "0..x", 3, { 0 => int!(0), 1 => operator!(".."), 2 => ident!("x") });
+test!(operator_range_between_identifiers, "a..b", 3, { 0 => ident!("a"), 1 => operator!(".."), 2 => ident!("b") });
 test!(operator_single_char, "%", { 0 => operator!("%")});
 test!(operator_multiple_chars, "::", { 0 => operator!("::") });
 test!(operator_multiple, "++::--", { 0 => operator!("++"), 1 => operator!("::"), 2 => operator!("--") });
+test!(operator_power_and_pipe, "a**-b|>f", { 1 => operator!("**"), 2 => operator!("-"), 4 => operator!("|>") });
+test!(operator_separator, "a;b", 3, { 0 => ident!("a"), 1 => operator!(";"), 2 => ident!("b") });
+test!(operator_comma, "f(a,b)", 6, { 1 => bracket!('('), 3 => operator!(","), 5 => bracket!(')') });
 test!(combined, "++test * (a-b).c", {
   0 => operator!("++"),
   1 => ident!("test"),
@@ -75,3 +140,53 @@ test!(combined, "++test * (a-b).c", {
   8 => operator!("."),
   9 => ident!("c")
 });
+
+test!(line_comment, "a // b + c\nd", 2, { 0 => ident!("a"), 1 => ident!("d") });
+test!(line_comment_at_the_end, "a //", 1, { 0 => ident!("a") });
+test!(line_comment_after_operator, "a +// b", 2, { 0 => ident!("a"), 1 => operator!("+") });
+test!(division_is_not_comment, "a / b", 3, { 1 => operator!("/") });
+test!(block_comment, "a /* b */ c", 2, { 0 => ident!("a"), 1 => ident!("c") });
+test!(block_comment_multiline, "a /* b\n c */ d", 2, { 0 => ident!("a"), 1 => ident!("d") });
+test!(block_comment_nested, "a /* b /* c */ d */ e", 2, { 0 => ident!("a"), 1 => ident!("e") });
+test!(block_comment_between_tokens, "1/**/2", 2, { 0 => int!(1), 1 => int!(2) });
+test!(block_comment_unterminated, "a /* b /* c */", 2, {
+  0 => ident!("a"),
+  1 => error!("/* b /* c */", ErrorCode::UnterminatedBlockComment)
+});
+test!(shebang, "#!/usr/bin/env nolang\na", 1, { 0 => ident!("a") });
+test!(shebang_not_on_first_line, "a\n#!b", 4, {
+  0 => ident!("a"),
+  1 => error!("#", ErrorCode::UnexpectedToken),
+  2 => operator!("!"),
+  3 => ident!("b")
+});
+
+#[test]
+fn locations_count_bytes_chars_and_utf16() {
+  let tokens = from_string("'ü' + 😀\n  ab");
+  let span = |index: usize| {
+    let token = &tokens[index];
+    (
+      token.start.byte,
+      token.start.line,
+      token.start.column,
+      token.end.column,
+      token.end.utf16_column,
+    )
+  };
+
+  assert_eq!(span(0), (0, 0, 0, 3, 3));
+  assert_eq!(span(1), (5, 0, 4, 5, 5));
+  assert_eq!(span(2), (7, 0, 6, 7, 8));
+  assert_eq!(span(3), (14, 1, 2, 4, 4));
+}
+
+#[test]
+fn tokenises_lazily() {
+  let source_code = SourceCode::new("a = 'unterminated".to_string());
+  let mut tokeniser = Tokeniser::new(&source_code);
+
+  assert_eq!(tokeniser.next().map(|token| token.value), Some(ident!("a")));
+  assert_eq!(tokeniser.next().map(|token| token.value), Some(operator!("=")));
+  assert_eq!(tokeniser.count(), 1);
+}
@@ -23,6 +23,18 @@ macro_rules! ident {
   };
 }
 
+/// Returns Keyword token
+///
+/// # Examples
+/// ```
+/// let token = keyword!("while")
+/// ```
+macro_rules! keyword {
+  ($value: expr) => {
+    crate::tokeniser::token::TokenValue::Keyword($value.to_string())
+  };
+}
+
 /// Returns Bracket token
 ///
 /// # Examples
@@ -101,5 +113,6 @@ pub(crate) use error;
 pub(crate) use float;
 pub(crate) use ident;
 pub(crate) use int;
+pub(crate) use keyword;
 pub(crate) use operator;
 pub(crate) use string;
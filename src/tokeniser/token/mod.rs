@@ -17,6 +17,8 @@ pub enum ErrorCode {
   UnterminatedStringLiteral,
   IntLiteralTooLong,
   FloatLiteralTooLong,
+  MalformedNumberLiteral,
+  UnterminatedBlockComment,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -26,6 +28,7 @@ pub enum TokenValue {
   IntLiteral(u128),
   FloatLiteral(f64),
   Identifier(String),
+  Keyword(String),
   Operator(String),
   Bracket(char),
   Error(String, ErrorCode),
@@ -44,6 +47,27 @@ impl Display for Token {
   }
 }
 
+impl Display for ErrorCode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let error = match self {
+      ErrorCode::UnterminatedCharLiteral => "Unterminated char literal",
+      ErrorCode::EmptyCharLiteral => "Empty char literal",
+      ErrorCode::CharLiteralTooLong => "Char literal is too long",
+      ErrorCode::BrokenUnicodeSequence => "Broken unicode escape sequence",
+      ErrorCode::UnknownEscapeSequence => "Unknown escape sequence",
+      ErrorCode::UnexpectedToken => "Unexpected Token",
+      ErrorCode::BrokenStringLiteral => "Broken string literal",
+      ErrorCode::UnterminatedStringLiteral => "Unterminated string literal",
+      ErrorCode::IntLiteralTooLong => "Int literal is too long",
+      ErrorCode::FloatLiteralTooLong => "Float literal is too long",
+      ErrorCode::MalformedNumberLiteral => "Malformed number literal",
+      ErrorCode::UnterminatedBlockComment => "Unterminated block comment",
+    };
+
+    write!(f, "{}", error)
+  }
+}
+
 impl Display for TokenValue {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     let value = match self {
@@ -52,6 +76,7 @@ impl Display for TokenValue {
       TokenValue::IntLiteral(i) => i.to_string(),
       TokenValue::FloatLiteral(f) => f.to_string(),
       TokenValue::Identifier(id) => id.to_string(),
+      TokenValue::Keyword(keyword) => keyword.to_string(),
       TokenValue::Operator(op) => op.to_string(),
       TokenValue::Bracket(b) => b.to_string(),
       TokenValue::Error(s, _err) => s.to_string(),
@@ -66,18 +91,20 @@ impl Token {
   }
 
   pub fn is_identifier(&self) -> bool {
-    match self.value {
-      TokenValue::Identifier(_) => true,
-      _ => false,
-    }
+    matches!(self.value, TokenValue::Identifier(_))
+  }
+
+  pub fn is_keyword(&self, keyword: &str) -> bool {
+    matches!(&self.value, TokenValue::Keyword(k) if k == keyword)
   }
 
   pub fn is_literal(&self) -> bool {
-    match self.value {
+    match &self.value {
       TokenValue::CharLiteral(_)
       | TokenValue::StringLiteral(_)
       | TokenValue::IntLiteral(_)
       | TokenValue::FloatLiteral(_) => true,
+      TokenValue::Keyword(keyword) => ["true", "false", "null"].contains(&keyword.as_str()),
       _ => false,
     }
   }
@@ -1,4 +1,4 @@
-use crate::source_code::SourceCode;
+use crate::source_code::{Location, SourceCode};
 
 use super::{
   check, parsers,
@@ -9,72 +9,84 @@ use super::{
 
 pub struct Tokeniser<'a> {
   source_code: &'a SourceCode,
-  position: usize,
-  current_token_start: usize,
+  cursor: Location,
+  token_start: Location,
 }
 
 impl<'a> Tokeniser<'a> {
-  pub fn new(source_code: &SourceCode) -> Tokeniser {
+  pub fn new(source_code: &SourceCode) -> Tokeniser<'_> {
+    let start = Location {
+      file: source_code.file(),
+      ..Location::default()
+    };
+
     Tokeniser {
       source_code,
-      position: 0,
-      current_token_start: 0,
+      cursor: start,
+      token_start: start,
     }
   }
 
   fn start_token(&mut self) {
-    self.current_token_start = self.position;
+    self.token_start = self.cursor;
   }
 
-  fn get_char(&self, position: usize) -> Option<char> {
-    self.source_code.code.chars().nth(position)
+  fn rest(&self) -> &'a str {
+    &self.source_code.code[self.cursor.byte..]
   }
 
-  fn commit_token<F>(&mut self, value: F) -> Token
-  where
-    F: FnOnce(String) -> TokenValue,
-  {
-    let code = &self.source_code.code;
+  fn peek(&self) -> Option<char> {
+    self.rest().chars().next()
+  }
 
-    macro_rules! nth_indices {
-      ($n: expr) => {
-        match code.char_indices().nth($n) {
-          Some((c, _)) => c,
-          None => code.len(),
-        }
-      };
+  fn peek_next(&self) -> Option<char> {
+    self.rest().chars().nth(1)
+  }
+
+  fn advance(&mut self) {
+    if let Some(c) = self.peek() {
+      self.cursor.advance(c);
     }
+  }
 
-    let from = nth_indices!(self.current_token_start);
-    let to = nth_indices!(self.position);
+  fn advance_by(&mut self, count: usize) {
+    for _ in 0..count {
+      self.advance();
+    }
+  }
 
-    let start = self.source_code.to_location(self.current_token_start).unwrap();
-    let end = self.source_code.to_location(self.position).unwrap();
+  fn commit_token<F>(&mut self, value: F) -> Token
+  where
+    F: FnOnce(String) -> TokenValue,
+  {
+    let code = &self.source_code.code[self.token_start.byte..self.cursor.byte];
 
-    let value = value(code[from..to].to_string());
-    Token::new(value, start, end)
+    Token::new(value(code.to_string()), self.token_start, self.cursor)
   }
 
   fn consume_identifier(&mut self) -> Token {
     self.start_token();
-    while let Some(c) = self.get_char(self.position) {
+    while let Some(c) = self.peek() {
       if c.is_alphanumeric() || c == '_' {
-        self.position += 1;
+        self.advance();
       } else {
         break;
       }
     }
 
-    self.commit_token(|s| TokenValue::Identifier(s))
+    self.commit_token(|s| match check::is_keyword(&s) {
+      true => TokenValue::Keyword(s),
+      false => TokenValue::Identifier(s),
+    })
   }
 
   fn consume_char_literal(&mut self) -> Token {
     self.start_token();
     let mut closed = false;
-    self.position += 1;
+    self.advance();
 
     let mut escape = false;
-    while let Some(c) = self.get_char(self.position) {
+    while let Some(c) = self.peek() {
       if c == '\n' {
         return self.commit_token(|s| TokenValue::Error(s, ErrorCode::UnterminatedCharLiteral));
       } else if c == '\'' && !escape {
@@ -85,27 +97,23 @@ impl<'a> Tokeniser<'a> {
       } else {
         escape = false;
       }
-      self.position += 1;
+      self.advance();
     }
 
-    self.position += 1;
+    self.advance();
 
     self.commit_token(|s| match closed {
       false => TokenValue::Error(s, ErrorCode::UnterminatedCharLiteral),
-      true => match s.chars().count() {
-        0 | 1 => unreachable!("Cannot happen"),
-        2 => TokenValue::Error(s, ErrorCode::EmptyCharLiteral),
-        3 => TokenValue::CharLiteral(s.chars().nth(1).unwrap()),
-        len => match parsers::parse_symbol_in_string(&s, 1, true) {
-          Err(e) => TokenValue::Error(s, e),
-          Ok((c, next)) => {
-            if next + 1 < len {
-              TokenValue::Error(s, ErrorCode::CharLiteralTooLong)
-            } else {
-              TokenValue::CharLiteral(c)
-            }
+      true if s == "''" => TokenValue::Error(s, ErrorCode::EmptyCharLiteral),
+      true => match parsers::parse_symbol_in_string(&s, 1, true) {
+        Err(e) => TokenValue::Error(s, e),
+        Ok((c, next)) => {
+          if next + 1 < s.len() {
+            TokenValue::Error(s, ErrorCode::CharLiteralTooLong)
+          } else {
+            TokenValue::CharLiteral(c)
           }
-        },
+        }
       },
     })
   }
@@ -124,7 +132,7 @@ impl<'a> Tokeniser<'a> {
     self.start_token();
     let mut state = 0;
 
-    while let Some(c) = self.get_char(self.position) {
+    while let Some(c) = self.peek() {
       if c == '\n' && state != 4 {
         return self.commit_token(|s| TokenValue::Error(s, ErrorCode::UnterminatedStringLiteral));
       };
@@ -139,13 +147,17 @@ impl<'a> Tokeniser<'a> {
         7 => select!(c, 10, 11),
         _ => unreachable!("Should not be called ever"),
       };
-      self.position += 1;
-      if state > 9 {
+      // 11 - the literal has ended before the current character
+      if state == 11 {
+        break;
+      }
+      self.advance();
+      if state > 8 {
         break;
       }
     }
     self.commit_token(|s: String| match state {
-      7 | 9 | 11 => match parsers::parse_string(s.trim_matches('\"').to_string()) {
+      2 | 7 | 9 | 11 => match parsers::parse_string(s.trim_matches('\"').to_string()) {
         Ok(s) => TokenValue::StringLiteral(s),
         Err(e) => TokenValue::Error(s, e),
       },
@@ -156,12 +168,14 @@ impl<'a> Tokeniser<'a> {
 
   fn restore(&mut self) -> Token {
     self.start_token();
+    // the broken token has at least one character, otherwise the tokeniser would get stuck
+    self.advance();
 
-    while let Some(c) = self.get_char(self.position) {
+    while let Some(c) = self.peek() {
       if c.is_whitespace() || check::is_punctuation(c) {
         break;
       }
-      self.position += 1;
+      self.advance();
     }
 
     self.commit_token(|s| TokenValue::Error(s, ErrorCode::UnexpectedToken))
@@ -170,7 +184,7 @@ impl<'a> Tokeniser<'a> {
   fn consume_bracket(&mut self) -> Token {
     self.start_token();
 
-    self.position += 1;
+    self.advance();
 
     self.commit_token(|s| match s.chars().nth(0) {
       Some(c) if check::is_bracket(c) => TokenValue::Bracket(c),
@@ -183,14 +197,14 @@ impl<'a> Tokeniser<'a> {
 
     let mut trie: &Trie = &check::OP_LIST;
 
-    while let Some(c) = self.get_char(self.position) {
+    while let Some(c) = self.peek() {
       trie = match trie.get(c) {
         Some(trie) => trie,
         None => {
           break;
         }
       };
-      self.position += 1;
+      self.advance();
     }
 
     self.commit_token(|s| {
@@ -202,142 +216,203 @@ impl<'a> Tokeniser<'a> {
     })
   }
 
+  /// Checks if the dot at the current position starts a range operator, so that `1..5` is not read as floats
+  fn is_range_next(&self) -> bool {
+    self.peek_next() == Some('.')
+  }
+
+  /// Consumes digits of the radix, which can be separated by single underscores like in `1_000`.
+  /// Returns the number of digits consumed.
+  fn consume_digits(&mut self, radix: u32) -> usize {
+    let mut count = 0;
+
+    while let Some(c) = self.peek() {
+      if c.is_digit(radix) {
+        count += 1;
+      } else if !(c == '_' && count > 0 && self.peek_next().is_some_and(|next| next.is_digit(radix))) {
+        break;
+      }
+      self.advance();
+    }
+
+    count
+  }
+
+  /// Consumes the exponent marker and the exponent after it, like `e-9`. Returns false if the exponent has no digits.
+  fn consume_exponent(&mut self) -> bool {
+    self.advance();
+
+    if matches!(self.peek(), Some('+' | '-')) {
+      self.advance();
+    }
+
+    self.consume_digits(10) > 0
+  }
+
+  /// Consumes a decimal number, returning how to parse it, or `None` if it is malformed
+  fn consume_decimal(&mut self) -> Option<fn(String) -> TokenValue> {
+    let leading_zero = self.peek() == Some('0');
+    let mut float = false;
+
+    self.consume_digits(10);
+
+    // the fraction is optional, as in `42.`
+    if self.peek() == Some('.') && !self.is_range_next() {
+      self.advance();
+      self.consume_digits(10);
+      float = true;
+    }
+
+    if matches!(self.peek(), Some('e' | 'E')) {
+      if !self.consume_exponent() {
+        return None;
+      }
+      float = true;
+    }
+
+    Some(match (float, leading_zero) {
+      (true, _) => parsers::parse_float,
+      // integers with a leading zero are octal, as in C
+      (false, true) => |s| parsers::parse_int(s, 8),
+      (false, false) => |s| parsers::parse_int(s, 10),
+    })
+  }
+
+  /// Consumes a number after the `0x`, `0o` or `0b` prefix, returning how to parse it, or `None` if it is malformed
+  fn consume_prefixed(&mut self, radix: u32) -> Option<fn(String) -> TokenValue> {
+    if self.consume_digits(radix) == 0 {
+      return None;
+    }
+
+    let fraction = self.peek() == Some('.') && !self.is_range_next();
+    if radix != 16 || !(fraction || matches!(self.peek(), Some('p' | 'P'))) {
+      return Some(match radix {
+        2 => |s| parsers::parse_int(s, 2),
+        8 => |s| parsers::parse_int(s, 8),
+        _ => |s| parsers::parse_int(s, 16),
+      });
+    }
+
+    if fraction {
+      self.advance();
+      self.consume_digits(16);
+    }
+
+    // hex floats always need a binary exponent, since `e` is a hex digit
+    match matches!(self.peek(), Some('p' | 'P')) && self.consume_exponent() {
+      true => Some(parsers::parse_hex_float),
+      false => None,
+    }
+  }
+
   fn consume_number_or_dot(&mut self) -> Token {
     self.start_token();
 
-    let mut state: u8 = 0;
-    let mut error = false;
+    if self.peek() == Some('.') && !self.peek_next().is_some_and(|c| c.is_ascii_digit()) {
+      self.advance_by(if self.is_range_next() { 2 } else { 1 });
 
-    while let Some(c) = self.get_char(self.position) {
-      state = match state {
-        // 0 - begin
-        0 => match c {
-          '0' => 1,
-          '1'..='9' => 2,
-          '.' => 3,
-          _ => unreachable!(),
-        },
-        // 1 - read '0'
-        1 => match c {
-          '0'..='7' => 4,
-          '.' => 5,
-          'x' => 6,
-          c if check::is_punctuation_or_whitespace(c) => {
-            break;
-          }
-          _ => panic!(),
-        },
-        // 2 - reading decimal
-        2 => match c {
-          '0'..='9' => 2,
-          '.' => 5,
-          c if check::is_bracket(c) || check::is_punctuation_or_whitespace(c) => {
-            break;
-          }
-          _ => {
-            error = true;
-            break;
-          }
-        },
-        // 3 - read '.'
-        3 => match c {
-          '0'..='9' => 5,
-          _ => {
-            break;
-          }
-        },
-        // 4 - reading octal
-        4 => match c {
-          '0'..='7' => 4,
-          c if check::is_punctuation_or_whitespace(c) => {
-            break;
-          }
-          _ => {
-            error = true;
-            break;
-          }
-        },
-        // 5 - reading float
-        5 => match c {
-          '0'..='9' => 5,
-          c if check::is_punctuation_or_whitespace(c) => {
-            break;
-          }
-          _ => {
-            error = true;
-            break;
-          }
-        },
-        // 6 - read "0x"
-        6 => match c {
-          '0' => 7,
-          c if c.is_ascii_hexdigit() => 8,
-          _ => {
-            error = true;
-            break;
-          }
-        },
-        // 7 - read "0x0"
-        7 => {
-          if check::is_punctuation_or_whitespace(c) {
-            break;
-          } else {
-            error = true;
-            break;
-          }
-        }
-        // 8 - reading hex
-        8 => match c {
-          c if c.is_ascii_hexdigit() => 8,
-          c if check::is_punctuation_or_whitespace(c) => {
-            break;
-          }
-          _ => {
-            error = true;
-            break;
-          }
-        },
-        _ => {
-          error = true;
-          break;
+      return self.commit_token(TokenValue::Operator);
+    }
+
+    let radix = match self.rest().get(..2) {
+      Some("0x") => 16,
+      Some("0o") => 8,
+      Some("0b") => 2,
+      _ => 10,
+    };
+
+    let parse = match radix {
+      10 => self.consume_decimal(),
+      _ => {
+        self.advance_by(2);
+        self.consume_prefixed(radix)
+      }
+    };
+
+    match parse {
+      Some(parse) if self.peek().is_none_or(check::is_token_boundary) => self.commit_token(parse),
+      // the rest of the word belongs to the broken number, like in `0b102` or `1_`
+      _ => {
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+          self.advance();
         }
-      };
 
-      self.position += 1;
+        self.commit_token(|s| TokenValue::Error(s, ErrorCode::MalformedNumberLiteral))
+      }
+    }
+  }
+
+  fn starts_with(&self, prefix: &str) -> bool {
+    self.rest().starts_with(prefix)
+  }
+
+  /// Skips the rest of the line, leaving the line break to separate statements
+  fn skip_line(&mut self) {
+    while let Some(c) = self.peek() {
+      if c == '\n' {
+        break;
+      }
+      self.advance();
     }
+  }
 
-    if error {
-      self.position = self.current_token_start;
-      return self.restore();
+  /// Skips a block comment, which can contain other block comments.
+  /// Returns an error token if the comment is not closed before the end of the code.
+  fn skip_block_comment(&mut self) -> Option<Token> {
+    self.start_token();
+    let mut depth = 0;
+
+    while self.peek().is_some() {
+      if self.starts_with("/*") {
+        depth += 1;
+        self.advance_by(2);
+      } else if self.starts_with("*/") {
+        depth -= 1;
+        self.advance_by(2);
+
+        if depth == 0 {
+          return None;
+        }
+      } else {
+        self.advance();
+      }
     }
 
-    self.commit_token(match state {
-      1 | 7 => |_| TokenValue::IntLiteral(0),
-      2 => |s| parsers::parse_int(s, 10),
-      3 => |_| TokenValue::Operator(".".to_string()),
-      4 => |s| parsers::parse_int(s, 8),
-      5 => parsers::parse_float,
-      8 => |s| parsers::parse_int(s, 16),
-      _ => |s| TokenValue::Error(s, ErrorCode::UnexpectedToken),
-    })
+    Some(self.commit_token(|s| TokenValue::Error(s, ErrorCode::UnterminatedBlockComment)))
   }
+}
+
+/// Tokens are produced one at a time, so the code is only tokenised as far as the parser has read it
+impl Iterator for Tokeniser<'_> {
+  type Item = Token;
 
-  pub fn parse(&mut self) -> Vec<Token> {
-    let mut tokens = Vec::new();
+  fn next(&mut self) -> Option<Token> {
+    // shebang is only allowed on the first line, so that scripts can be run directly
+    if self.cursor.byte == 0 && self.starts_with("#!") {
+      self.skip_line();
+    }
 
-    while let Some(c) = self.get_char(self.position) {
-      let to_push = if c.is_alphabetic() {
+    while let Some(c) = self.peek() {
+      let token = if self.starts_with("//") {
+        self.skip_line();
+        continue;
+      } else if self.starts_with("/*") {
+        match self.skip_block_comment() {
+          Some(error) => error,
+          None => continue,
+        }
+      } else if c.is_alphabetic() {
         self.consume_identifier()
       } else if c == '\'' {
         self.consume_char_literal()
       } else if c == '"' {
         self.consume_string_literal()
       } else if c.is_whitespace() || c == '\n' {
-        self.position += 1;
+        self.advance();
         continue;
       } else if check::is_bracket(c) {
         self.consume_bracket()
-      } else if c == '.' || c.is_ascii_hexdigit() {
+      } else if c == '.' || c.is_ascii_digit() {
         self.consume_number_or_dot()
       } else if check::is_punctuation(c) {
         self.consume_operator()
@@ -345,9 +420,9 @@ impl<'a> Tokeniser<'a> {
         self.restore()
       };
 
-      tokens.push(to_push);
+      return Some(token);
     }
 
-    tokens
+    None
   }
 }
@@ -24,18 +24,31 @@ impl Trie {
   pub fn from_op_list() -> Trie {
     Trie::from_list(&[
       "=", "==", "!=", ">", "<", ">=", "<=", // comparison
-      "+", "-", "*", "/", "%", // base arithmetic
+      "+", "-", "*", "/", "%", "**", // base arithmetic
       "++", "--", // increment & decrement
       "+=", "-=", "*=", "/=", "%=", // shorthand arithmetic
       "<<", ">>", "&", "|", "^", "~", // bit manipulation
-      "&&", "||", "!", // logical
-      "|>", "?", ":", "::", // to be determined if we need those
+      "&&", "||", "!",  // logical
+      "=>", // lambda
+      "?", ":",  // conditional
+      "|>", // pipe
+      "::", // to be determined if we need it
       "..", // range
       ".",  // member
+      ";",  // statement separator
+      ",",  // argument separator
     ])
   }
 
-  fn push_internal(self: &mut Self, word: &str, len: usize, idx: usize) -> Option<usize> {
+  pub fn from_keyword_list() -> Trie {
+    Trie::from_list(&[
+      "let", "fn", "return", // declarations
+      "if", "else", "while", "for", "in", "break", "continue", // control flow
+      "true", "false", "null", // literals
+    ])
+  }
+
+  fn push_internal(&mut self, word: &str, len: usize, idx: usize) -> Option<usize> {
     if idx == len {
       self.end = Some(len);
       return self.end;
@@ -43,20 +56,15 @@ impl Trie {
 
     let letter = word.chars().nth(idx)?;
 
-    if !self.children.contains_key(&letter) {
-      self.children.insert(letter, Trie::new());
-    }
+    self.children.entry(letter).or_insert_with(Trie::new);
 
     let trie = self.children.get_mut(&letter)?;
 
     trie.push_internal(word, len, idx + 1)
   }
 
-  pub fn push(self: &mut Self, word: &str) -> bool {
-    match self.push_internal(word, word.len(), 0) {
-      Some(_) => true,
-      _ => false,
-    }
+  pub fn push(&mut self, word: &str) -> bool {
+    self.push_internal(word, word.len(), 0).is_some()
   }
 
   fn has_internal(&self, word: &str, len: usize, idx: usize) -> Option<usize> {
@@ -123,18 +131,18 @@ mod test {
     trie.push("abc");
     trie.push("abd");
 
-    assert_eq!(trie.has("abc"), true);
-    assert_eq!(trie.has("abd"), true);
-    assert_eq!(trie.has("adc"), false);
+    assert!(trie.has("abc"));
+    assert!(trie.has("abd"));
+    assert!(!trie.has("adc"));
   }
   #[test]
   fn creates_from_list() {
     let trie = Trie::from_list(&["abc", "abd"]);
 
-    assert_eq!(trie.has("abc"), true);
-    assert_eq!(trie.has("abd"), true);
-    assert_eq!(trie.has("adc"), false);
-    assert_eq!(trie.has("abcd"), false);
-    assert_eq!(trie.has("ab"), false);
+    assert!(trie.has("abc"));
+    assert!(trie.has("abd"));
+    assert!(!trie.has("adc"));
+    assert!(!trie.has("abcd"));
+    assert!(!trie.has("ab"));
   }
 }
@@ -0,0 +1,73 @@
+//! Runs the `nolang` binary on script files, the way it is used from the command line.
+
+use std::{
+  fs,
+  path::PathBuf,
+  process::{Command, Output},
+};
+
+/// Writes the script into its own temporary file, runs it and removes the file again
+fn run_script(name: &str, code: &str) -> (Output, PathBuf) {
+  let path = std::env::temp_dir().join(format!("nolang-{}-{}.nl", std::process::id(), name));
+  fs::write(&path, code).unwrap();
+
+  let output = Command::new(env!("CARGO_BIN_EXE_nolang")).arg(&path).output().unwrap();
+  fs::remove_file(&path).unwrap();
+
+  (output, path)
+}
+
+fn stdout(output: &Output) -> String {
+  String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn stderr(output: &Output) -> String {
+  String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+#[test]
+fn prints_the_values_of_expressions() {
+  let (output, _) = run_script("values", "a = 2\nb = a * 3\na + b\nfn double(x) = x * 2\ndouble(b)\n");
+
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "8\n12\n");
+}
+
+#[test]
+fn stops_at_the_first_runtime_error() {
+  let (output, path) = run_script("runtime-error", "a = 1\na + b\nc = 2\nc\n");
+
+  assert!(!output.status.success());
+  assert_eq!(stdout(&output), "");
+  assert!(
+    stderr(&output).starts_with(&format!("error: variable `b` not found\n --> {}:2:5\n", path.display())),
+    "{}",
+    stderr(&output)
+  );
+}
+
+#[test]
+fn reports_syntax_errors_without_running_the_script() {
+  let (output, path) = run_script("syntax-error", "1 + 1\na = (2\n");
+
+  assert!(!output.status.success());
+  assert_eq!(stdout(&output), "");
+  assert!(
+    stderr(&output).contains(&format!(" --> {}:2:7\n", path.display())),
+    "{}",
+    stderr(&output)
+  );
+}
+
+#[test]
+fn reports_missing_files() {
+  let path = std::env::temp_dir().join("nolang-missing-file.nl");
+  let output = Command::new(env!("CARGO_BIN_EXE_nolang")).arg(&path).output().unwrap();
+
+  assert!(!output.status.success());
+  assert!(
+    stderr(&output).starts_with(&format!("{}: ", path.display())),
+    "{}",
+    stderr(&output)
+  );
+}
@@ -0,0 +1,188 @@
+//! Checks that no code, however broken, makes the tokeniser, the parser, the computer or the error renderer panic.
+
+use nolang::{ast, computer::Computer, source_code::SourceCode, stack};
+use proptest::prelude::*;
+
+/// Pieces of code which are joined in random order, so that the parser sees mostly valid tokens in invalid places
+const FRAGMENTS: &[&str] = &[
+  " ",
+  "\n",
+  ";",
+  ",",
+  "(",
+  ")",
+  "[",
+  "]",
+  "{",
+  "}",
+  "=",
+  "+=",
+  "-=",
+  "*=",
+  "/=",
+  "%=",
+  "++",
+  "--",
+  "+",
+  "-",
+  "*",
+  "/",
+  "%",
+  "**",
+  "!",
+  "~",
+  "&",
+  "|",
+  "^",
+  "<<",
+  ">>",
+  "==",
+  "!=",
+  "<",
+  ">",
+  "<=",
+  ">=",
+  "&&",
+  "||",
+  "|>",
+  "?",
+  ":",
+  ".",
+  "..",
+  "=>",
+  "let",
+  "fn",
+  "return",
+  "if",
+  "else",
+  "while",
+  "for",
+  "in",
+  "break",
+  "continue",
+  "true",
+  "false",
+  "null",
+  "a",
+  "b",
+  "f",
+  "x",
+  "sqrt",
+  "map",
+  "filter",
+  "max",
+  "pow",
+  "0",
+  "1",
+  "07",
+  "09",
+  "0x",
+  "0x1f",
+  "0b",
+  "0b2",
+  "1_",
+  "1e",
+  "2.5",
+  ".5",
+  "1e-3",
+  "0x1.8p1",
+  "'",
+  "'a'",
+  "''",
+  "\"",
+  "\"s\"",
+  "\"\\u00",
+  "'\\u",
+  "\\",
+  "/*",
+  "*/",
+  "//",
+  "#!",
+  "試",
+  "😀",
+  "é",
+  "\r",
+  "\t",
+  "170141183460469231731687303715884105727",
+  "340282366920938463463374607431768211456",
+];
+
+/// Parses and computes the code on a thread with as much stack as the interpreter gets
+fn run(code: &str, iteration_limit: usize) {
+  stack::run(|| {
+    let source_code = SourceCode::new(code.to_string());
+    let (ast, diagnostics) = ast::parse(&source_code);
+
+    for diagnostic in diagnostics {
+      source_code.render_error(&diagnostic.code, diagnostic.start, diagnostic.end);
+    }
+
+    let mut computer = Computer::new();
+    computer.set_iteration_limit(iteration_limit);
+
+    if let Err(error) = computer.compute(&ast) {
+      source_code.render_error(&error.kind, error.start, error.end);
+    }
+  })
+}
+
+fn code_from_fragments() -> impl Strategy<Value = String> {
+  prop::collection::vec(prop::sample::select(FRAGMENTS), 0..64).prop_map(|fragments| fragments.concat())
+}
+
+/// Code nested deeper than the stack can take, unless parsing and computing it stop in time
+fn deeply_nested_code() -> impl Strategy<Value = String> {
+  let nesting = prop::sample::select(
+    &[
+      ("(", ")"),
+      ("[", "]"),
+      ("{", "}"),
+      ("-", ""),
+      ("1 + ", ""),
+      ("2 ** ", ""),
+      ("if true {", "}"),
+      ("fn f(n) = {", "}"),
+    ][..],
+  );
+
+  (nesting, 0..12_000usize)
+    .prop_map(|((open, close), depth)| format!("{}f(n + 1){}; f(0)", open.repeat(depth), close.repeat(depth)))
+}
+
+/// Lists nested as deeply as the loop runs, which are then printed, compared or dropped
+fn deeply_nested_values() -> impl Strategy<Value = String> {
+  let wrap = prop::sample::select(&["[a]", "[1, [a]]", "map((x) => [x], [a])", "filter((x) => true, [a])"][..]);
+  let use_value = prop::sample::select(&["a", "a == a", "[a] != a", "a = 0"][..]);
+
+  (wrap, 0..200_000usize, use_value)
+    .prop_map(|(wrap, depth, use_value)| format!("a = []; for i in 0..{} {{ a = {} }}; {}", depth, wrap, use_value))
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(2000))]
+
+  #[test]
+  fn arbitrary_text_does_not_panic(code in any::<String>()) {
+    run(&code, 1000);
+  }
+
+  #[test]
+  fn arbitrary_tokens_do_not_panic(code in code_from_fragments()) {
+    run(&code, 1000);
+  }
+
+  #[test]
+  fn deeply_nested_values_do_not_overflow(code in deeply_nested_values()) {
+    run(&code, 200_000);
+  }
+}
+
+proptest! {
+  // every case is thousands of tokens long, so fewer of them are tried
+  #![proptest_config(ProptestConfig::with_cases(200))]
+
+  #[test]
+  fn deeply_nested_code_does_not_overflow(code in deeply_nested_code()) {
+    run(&code, 1000);
+  }
+}